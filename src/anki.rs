@@ -0,0 +1,242 @@
+use crate::csvfile::{vocab_from_record, Column};
+use crate::dict::Vocab;
+use crate::error::*;
+use rusqlite::Connection;
+use std::fs::{remove_file, File};
+use std::io::Read;
+
+/// the collections an Anki package can contain (newest first). `collection.anki21b` is
+/// compressed with zstd
+const COLLECTIONS: [&str; 3] = [
+    "collection.anki21b",
+    "collection.anki21",
+    "collection.anki2",
+];
+
+/// a note of an Anki deck that was not imported
+#[derive(Debug, Clone)]
+pub struct Unmapped {
+    /// the id of the note in the collection
+    pub id: i64,
+    /// the (stripped) first field of the note
+    pub first: String,
+    /// why the note was not imported
+    pub reason: String,
+}
+
+/// decode a HTML entity (without `&` and `;`)
+fn decode_entity(entity: &str) -> Option<char> {
+    match entity {
+        "nbsp" => Some(' '),
+        "amp" => Some('&'),
+        "lt" => Some('<'),
+        "gt" => Some('>'),
+        "quot" => Some('"'),
+        "apos" => Some('\''),
+        n if n.starts_with("#x") || n.starts_with("#X") => u32::from_str_radix(&n[2..], 16)
+            .ok()
+            .and_then(char::from_u32),
+        n if n.starts_with('#') => n[1..].parse::<u32>().ok().and_then(char::from_u32),
+        _ => None,
+    }
+}
+
+/// strip HTML, sound references and cloze markers from a field. Line breaks and blocks become
+/// commas so they seperate meanings
+/// # Arguments
+/// * `field`: the field of a note
+pub fn strip_html(field: &str) -> String {
+    let mut out: String = String::new();
+    let mut rest: &str = field;
+    while let Some(c) = rest.chars().next() {
+        if let (Some(end), '<') = (rest.find('>'), c) {
+            let tag: String = rest[1..end]
+                .trim_start_matches('/')
+                .split(|x: char| x.is_whitespace() || x == '/')
+                .next()
+                .unwrap_or("")
+                .to_lowercase();
+            if ["br", "div", "p", "li"].contains(&tag.as_str()) {
+                out.push(',');
+            }
+            rest = &rest[end + 1..];
+        } else if c == '&' {
+            match rest.find(';') {
+                Some(end) if end < 10 => match decode_entity(&rest[1..end]) {
+                    Some(n) => {
+                        out.push(n);
+                        rest = &rest[end + 1..];
+                    }
+                    None => {
+                        out.push(c);
+                        rest = &rest[1..];
+                    }
+                },
+                _ => {
+                    out.push(c);
+                    rest = &rest[1..];
+                }
+            }
+        } else if rest.starts_with("[sound:") {
+            rest = match rest.find(']') {
+                Some(end) => &rest[end + 1..],
+                None => "",
+            };
+        } else if rest.starts_with("{{c") && rest.contains("::") {
+            // {{c1::answer::hint}} becomes answer
+            let end: usize = rest.find("}}").map(|x| x + 2).unwrap_or(rest.len());
+            let inner: &str = rest[..end].trim_end_matches("}}");
+            out.push_str(inner.split("::").nth(1).unwrap_or(""));
+            rest = &rest[end..];
+        } else {
+            out.push(c);
+            rest = &rest[c.len_utf8()..];
+        }
+    }
+    out.split(',')
+        .map(|x| x.split_whitespace().collect::<Vec<&str>>().join(" "))
+        .filter(|x| !x.is_empty())
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+/// extract the collection of an Anki package to a temporary file
+/// # Arguments
+/// * `path`: the path to the `.apkg` or `.colpkg` file
+/// # Returns
+/// the path to the temporary file
+fn extract_collection(path: &str) -> Result<String, VctError> {
+    let file = match File::open(path) {
+        Ok(n) => n,
+        Err(e) => {
+            return Err(VctError::new(
+                VctErrorKind::FileError,
+                format!("error opening '{}': {}", path, e).as_str(),
+            ));
+        }
+    };
+    let mut archive = match zip::ZipArchive::new(file) {
+        Ok(n) => n,
+        Err(e) => {
+            return Err(VctError::new(
+                VctErrorKind::ParsingError,
+                format!("'{}' is not an Anki package: {}", path, e).as_str(),
+            ));
+        }
+    };
+    for name in COLLECTIONS {
+        let mut entry = match archive.by_name(name) {
+            Ok(n) => n,
+            Err(_) => continue,
+        };
+        let mut data: Vec<u8> = Vec::new();
+        if let Err(e) = entry.read_to_end(&mut data) {
+            return Err(VctError::new(
+                VctErrorKind::FileError,
+                format!("error reading {}: {}", name, e).as_str(),
+            ));
+        }
+        if name.ends_with('b') {
+            data = match zstd::decode_all(data.as_slice()) {
+                Ok(n) => n,
+                Err(e) => {
+                    return Err(VctError::new(
+                        VctErrorKind::ParsingError,
+                        format!("error decompressing {}: {}", name, e).as_str(),
+                    ));
+                }
+            };
+        }
+        let out: String = std::env::temp_dir()
+            .join(format!("vct-anki-{}.db", std::process::id()))
+            .to_string_lossy()
+            .to_string();
+        if let Err(e) = std::fs::write(out.as_str(), data) {
+            return Err(VctError::new(
+                VctErrorKind::FileError,
+                format!("error extracting collection: {}", e).as_str(),
+            ));
+        }
+        return Ok(out);
+    }
+    Err(VctError::new(
+        VctErrorKind::ParsingError,
+        format!("'{}' doesn't contain an Anki collection", path).as_str(),
+    ))
+}
+
+/// read the notes of a collection as `(id, fields, tags)`
+fn read_notes(path: &str) -> Result<Vec<(i64, Vec<String>, String)>, VctError> {
+    let db_error = |e: rusqlite::Error| {
+        VctError::new(
+            VctErrorKind::DatabaseError,
+            format!("error reading Anki collection: {}", e).as_str(),
+        )
+    };
+    let conn = Connection::open(path).map_err(db_error)?;
+    let mut sel = conn
+        .prepare("SELECT id, flds, tags FROM notes ORDER BY id")
+        .map_err(db_error)?;
+    let notes = sel
+        .query_map([], |row| {
+            let fields: String = row.get(1)?;
+            Ok((
+                row.get(0)?,
+                fields.split('\x1f').map(|x| x.to_string()).collect(),
+                row.get(2)?,
+            ))
+        })
+        .map_err(db_error)?;
+    notes
+        .collect::<Result<Vec<(i64, Vec<String>, String)>, rusqlite::Error>>()
+        .map_err(db_error)
+}
+
+/// import the notes of an Anki package. The fields of a note are mapped like the columns of a
+/// CSV file and the tags of the note become the tags of the vocab unless a field is mapped to
+/// them
+/// # Arguments
+/// * `path`: the path to the `.apkg` or `.colpkg` file
+/// * `columns`: what the fields of the notes contain (in the order of the note type)
+/// # Returns
+/// the imported vocabulary and the notes that couldn't be mapped
+pub fn import(path: &str, columns: &[Column]) -> Result<(Vec<Vocab>, Vec<Unmapped>), VctError> {
+    let collection: String = extract_collection(path)?;
+    let notes = read_notes(collection.as_str());
+    let _ = remove_file(collection.as_str());
+    let note_tags: bool = !columns.contains(&Column::Tags);
+    let mut note_columns: Vec<Column> = columns.to_vec();
+    if note_tags {
+        note_columns.push(Column::Tags);
+    }
+    let mut vocab: Vec<Vocab> = Vec::new();
+    let mut unmapped: Vec<Unmapped> = Vec::new();
+    for (id, fields, tags) in notes? {
+        let mut record: csv::StringRecord = fields
+            .iter()
+            .take(columns.len())
+            .map(|x| strip_html(x))
+            .collect();
+        if note_tags {
+            // pad the fields so the tags end up in the last column
+            while record.len() < columns.len() {
+                record.push_field("");
+            }
+            record.push_field(
+                tags.split_whitespace()
+                    .collect::<Vec<&str>>()
+                    .join(",")
+                    .as_str(),
+            );
+        }
+        match vocab_from_record(&record, &note_columns) {
+            Ok(n) => vocab.push(n),
+            Err(reason) => unmapped.push(Unmapped {
+                id,
+                first: record.get(0).unwrap_or("").to_string(),
+                reason,
+            }),
+        }
+    }
+    Ok((vocab, unmapped))
+}
@@ -1,3 +1,4 @@
+use crate::error::*;
 use crate::info;
 use btui::Terminal;
 use std::env::args;
@@ -15,6 +16,28 @@ pub struct Params {
     pub clearlines: Option<bool>,
     pub usedb: Option<bool>,
     pub query: Option<String>,
+    pub due: bool,
+    pub scheduler: Option<String>,
+    pub direction: Option<String>,
+    pub choice: bool,
+    pub learning_loop: Option<bool>,
+    pub limit: Option<usize>,
+    pub minutes: Option<u64>,
+    pub select: Option<String>,
+    pub tags: Vec<String>,
+    pub exclude_tags: Vec<String>,
+    pub command: Option<String>,
+    pub command_args: Vec<String>,
+    /// the output format of -q, -p and sessions
+    pub format: Option<String>,
+    /// the format of files imported or exported (`csv` or `tsv`)
+    pub delimiter: Option<String>,
+    pub columns: Option<String>,
+    pub header: bool,
+    pub anki: Option<String>,
+    pub dry_run: bool,
+    pub keep: bool,
+    pub lenient: bool,
 }
 
 impl Params {
@@ -35,11 +58,62 @@ impl Params {
             clearlines: None,
             usedb: None,
             query: None,
+            due: false,
+            scheduler: None,
+            direction: None,
+            choice: false,
+            learning_loop: None,
+            limit: None,
+            minutes: None,
+            select: None,
+            tags: Vec::new(),
+            exclude_tags: Vec::new(),
+            command: None,
+            command_args: Vec::new(),
+            format: None,
+            delimiter: None,
+            columns: None,
+            header: true,
+            anki: None,
+            dry_run: false,
+            keep: false,
+            lenient: false,
         }
     }
 }
 
-pub fn load_params() -> Params {
+/// get the arguments that are neither options nor the values of options
+/// # Arguments
+/// * `arguments`: all arguments including the name of the program
+fn positional(arguments: &[String]) -> Vec<String> {
+    let mut out: Vec<String> = Vec::new();
+    let mut idx: usize = 1;
+    while idx < arguments.len() {
+        let arg: &str = arguments[idx].as_str();
+        if VALUE_OPTIONS.contains(&arg) {
+            idx += 2;
+        } else if arg == "-d" || arg == "--dict" {
+            // the dict, name and meanings are followed by optional additionals and tags
+            idx += 4;
+            for _ in 0..2 {
+                if idx < arguments.len() && !arguments[idx].starts_with('-') {
+                    idx += 1;
+                }
+            }
+        } else {
+            if !arg.starts_with('-') {
+                out.push(arg.to_string());
+            }
+            idx += 1;
+        }
+    }
+    out
+}
+
+/// parse the arguments vct was started with
+/// # Returns
+/// the parameters or an error if an argument is neither an option nor a command
+pub fn load_params() -> Result<Params, VctError> {
     let arguments: Vec<String> = args().collect();
     let mut params: Params = Params::new();
     if arguments.len() < 2 {
@@ -47,6 +121,23 @@ pub fn load_params() -> Params {
         params.quit = true;
     }
     let term = Terminal::new();
+    // the first argument that isn't an option is the command, the following ones its arguments
+    let mut positional = positional(&arguments).into_iter();
+    if let Some(n) = positional.next() {
+        if !COMMANDS.contains(&n.as_str()) {
+            return Err(VctError::new(
+                VctErrorKind::ParamError,
+                format!(
+                    "'{}' is not a command. Valid are {} (see `vct --help`)",
+                    n,
+                    COMMANDS.join(", ")
+                )
+                .as_str(),
+            ));
+        }
+        params.command = Some(n);
+        params.command_args = positional.collect();
+    }
     for (idx, arg) in arguments.clone().into_iter().enumerate() {
         match arg.as_str() {
             "-h" | "--help" => {
@@ -79,14 +170,14 @@ pub fn load_params() -> Params {
                 }
             }
             "-d" | "--dict" => {
-                if (arguments.len() - 3) > idx {
+                if idx + 3 < arguments.len() {
                     params.dict = format!(
                         "{};{};{}",
                         arguments[idx + 1usize].clone(),
                         arguments[idx + 2usize].clone(),
                         arguments[idx + 3usize].clone()
                     );
-                    if (arguments.len() - 4) > idx {
+                    if idx + 4 < arguments.len() {
                         if arguments[idx + 4usize].clone().starts_with('-') {
                             continue;
                         } else {
@@ -97,6 +188,13 @@ pub fn load_params() -> Params {
                                 arguments[idx + 3usize].clone(),
                                 arguments[idx + 4usize].clone()
                             );
+                            if idx + 5 < arguments.len()
+                                && !arguments[idx + 5usize].starts_with('-')
+                            {
+                                params.dict.push_str(
+                                    format!(";{}", arguments[idx + 5usize].clone()).as_str(),
+                                );
+                            }
                         }
                     }
                 } else {
@@ -107,15 +205,13 @@ pub fn load_params() -> Params {
                     );
                 }
             }
-            "-V" | "--vocab" => {
-                if (arguments.len() - 1) > idx {
-                    params.vocab = match arguments[idx + 1usize].clone().as_str() {
-                        "all" => String::from("all"),
-                        "one" => String::from("one"),
-                        n => {
-                            info::print_info(&term, format!("'{}' is not valid as a vocab parameter. Valid are 'one' and 'all'. Using default", n), info::MessageType::Warning);
-                            String::new()
-                        }
+            "-V" | "--vocab" if (arguments.len() - 1) > idx => {
+                params.vocab = match arguments[idx + 1usize].clone().as_str() {
+                    "all" => String::from("all"),
+                    "one" => String::from("one"),
+                    n => {
+                        info::print_info(&term, format!("'{}' is not valid as a vocab parameter. Valid are 'one' and 'all'. Using default", n), info::MessageType::Warning);
+                        String::new()
                     }
                 }
             }
@@ -151,24 +247,252 @@ pub fn load_params() -> Params {
                     info::print_info(&term, "no query provided", info::MessageType::Warning);
                 }
             }
+            "--reverse" => {
+                params.direction = Some(String::from("reverse"));
+            }
+            "--mixed" => {
+                params.direction = Some(String::from("mixed"));
+            }
+            "-c" | "--choice" => {
+                params.choice = true;
+            }
+            "--loop" => {
+                params.learning_loop = Some(true);
+            }
+            "--noloop" => {
+                params.learning_loop = Some(false);
+            }
+            "-n" | "--limit" if (arguments.len() - 1) > idx => {
+                params.limit = match arguments[idx + 1usize].parse::<usize>() {
+                    Ok(n) => Some(n),
+                    Err(_) => {
+                        info::print_info(
+                            &term,
+                            format!(
+                                "'{}' is not a valid limit. Using default",
+                                arguments[idx + 1usize]
+                            ),
+                            info::MessageType::Warning,
+                        );
+                        None
+                    }
+                }
+            }
+            "--minutes" if (arguments.len() - 1) > idx => {
+                params.minutes = match arguments[idx + 1usize].parse::<u64>() {
+                    Ok(n) => Some(n),
+                    Err(_) => {
+                        info::print_info(
+                            &term,
+                            format!(
+                                "'{}' is not a valid amount of minutes. Using default",
+                                arguments[idx + 1usize]
+                            ),
+                            info::MessageType::Warning,
+                        );
+                        None
+                    }
+                }
+            }
+            "--select" if (arguments.len() - 1) > idx => {
+                params.select = match arguments[idx + 1usize].clone().as_str() {
+                    "random" => Some(String::from("random")),
+                    "oldest" => Some(String::from("oldest")),
+                    "most-missed" => Some(String::from("most-missed")),
+                    "newest" => Some(String::from("newest")),
+                    n => {
+                        info::print_info(&term, format!("'{}' is not valid as a selection strategy. Valid are 'random', 'oldest', 'most-missed' and 'newest'. Using default", n), info::MessageType::Warning);
+                        None
+                    }
+                }
+            }
+            "-t" | "--tag" => {
+                if (arguments.len() - 1) > idx {
+                    for tag in arguments[idx + 1usize].split(',') {
+                        params.tags.push(tag.trim().to_string());
+                    }
+                } else {
+                    info::print_info(&term, "no tags provided", info::MessageType::Warning);
+                }
+            }
+            "--exclude-tag" => {
+                if (arguments.len() - 1) > idx {
+                    for tag in arguments[idx + 1usize].split(',') {
+                        params.exclude_tags.push(tag.trim().to_string());
+                    }
+                } else {
+                    info::print_info(&term, "no tags provided", info::MessageType::Warning);
+                }
+            }
+            "--due" => {
+                params.due = true;
+            }
+            "--scheduler" if (arguments.len() - 1) > idx => {
+                params.scheduler = match arguments[idx + 1usize].clone().as_str() {
+                    "sm2" => Some(String::from("sm2")),
+                    "leitner" => Some(String::from("leitner")),
+                    n => {
+                        info::print_info(&term, format!("'{}' is not valid as a scheduler. Valid are 'sm2' and 'leitner'. Using default", n), info::MessageType::Warning);
+                        None
+                    }
+                }
+            }
+            "--format" if (arguments.len() - 1) > idx => {
+                params.format = match arguments[idx + 1usize].as_str() {
+                    "json" => Some(String::from("json")),
+                    "tsv" => Some(String::from("tsv")),
+                    n => {
+                        return Err(VctError::new(
+                            VctErrorKind::ParamError,
+                            format!(
+                                "'{}' is not valid as an output format. Valid are 'json' and 'tsv' (--delimiter sets the format of imported and exported files)",
+                                n
+                            )
+                            .as_str(),
+                        ));
+                    }
+                }
+            }
+            "--delimiter" if (arguments.len() - 1) > idx => {
+                params.delimiter = match arguments[idx + 1usize].as_str() {
+                    "csv" => Some(String::from("csv")),
+                    "tsv" => Some(String::from("tsv")),
+                    n => {
+                        return Err(VctError::new(
+                            VctErrorKind::ParamError,
+                            format!(
+                                "'{}' is not valid as a file format. Valid are 'csv' and 'tsv'",
+                                n
+                            )
+                            .as_str(),
+                        ));
+                    }
+                }
+            }
+            "--columns" => {
+                if (arguments.len() - 1) > idx {
+                    params.columns = Some(arguments[idx + 1usize].clone());
+                } else {
+                    info::print_info(&term, "no columns provided", info::MessageType::Warning);
+                }
+            }
+            "--anki" => {
+                if (arguments.len() - 1) > idx {
+                    params.anki = Some(arguments[idx + 1usize].clone());
+                } else {
+                    info::print_info(
+                        &term,
+                        "no Anki package provided",
+                        info::MessageType::Warning,
+                    );
+                }
+            }
+            "--dry-run" => {
+                params.dry_run = true;
+            }
+            "--keep" => {
+                params.keep = true;
+            }
+            "--lenient" => {
+                params.lenient = true;
+            }
+            "--noheader" => {
+                params.header = false;
+            }
             _ => (),
         }
     }
-    params
+    if params.quit {
+        return Ok(params);
+    }
+    check_formats(&params)?;
+    Ok(params)
+}
+
+/// make sure `--format` and `--delimiter` are only used where they apply
+fn check_formats(params: &Params) -> Result<(), VctError> {
+    let files: bool = matches!(params.command.as_deref(), Some("import") | Some("export"));
+    // without a command -q, -p and sessions print results (`-d` only adds a vocab)
+    let output: bool =
+        params.command.is_none() && (params.query.is_some() || params.dict.is_empty());
+    if params.format.is_some() && !output {
+        return Err(VctError::new(
+            VctErrorKind::ParamError,
+            if files {
+                "--format sets the output of -q, -p and sessions. Use --delimiter for the format of imported and exported files"
+            } else {
+                "--format only applies to -q, -p and learning sessions"
+            },
+        ));
+    }
+    if params.delimiter.is_some() && (!files || params.anki.is_some()) {
+        return Err(VctError::new(
+            VctErrorKind::ParamError,
+            "--delimiter only applies to importing and exporting CSV/TSV files",
+        ));
+    }
+    Ok(())
 }
 
+/// all options that are followed by a value (`-d` is followed by up to five)
+const VALUE_OPTIONS: [&str; 22] = [
+    "--config",
+    "-l",
+    "--lang",
+    "--config-dir",
+    "-V",
+    "--vocab",
+    "-p",
+    "--pretty",
+    "-q",
+    "--query",
+    "-n",
+    "--limit",
+    "--minutes",
+    "--select",
+    "-t",
+    "--tag",
+    "--exclude-tag",
+    "--scheduler",
+    "--format",
+    "--delimiter",
+    "--columns",
+    "--anki",
+];
+
+/// all commands vct understands (the first argument that isn't an option)
+const COMMANDS: [&str; 11] = [
+    "stats", "import", "export", "migrate", "edit", "rm", "mv", "editor", "header", "check",
+    "dedupe",
+];
+
 const HELP_STR: &str = "
 Synopsis:
     vct [-hv] [-l <lang>] [-p <lang>]
+    vct [OPTIONS] <command> [ARGS] [OPTIONS]
 Usage:
   vct [OPTIONS]
+Commands:
+  stats [lang]: show the accuracy of past sessions per language and week (or per week and vocab of [lang])
+  import <file> -l <lang>: add the vocabulary of a CSV/TSV file to <lang>
+  import --anki <package> -l <lang>: add the notes of an Anki package (.apkg or .colpkg) to <lang>
+  export <lang> <file>: write the vocabulary of <lang> to a CSV/TSV file
+  migrate <to-db|to-dict> [langs]: move [langs] (default: all) from the dict files into the database or back
+  edit <lang> <vocab> <field> <value> [<field> <value>...]: change the name, meanings, additionals or tags of <vocab>
+  rm <lang> <vocab>: remove <vocab> from <lang>
+  mv <lang> <vocab> <newlang>: move <vocab> from <lang> to <newlang>
+  (<vocab> is the name of a vocabulary or, with --db, @<id> with the id shown by edit, rm and mv)
+  editor <lang>: browse, filter and change the vocabulary of <lang> in a full screen editor
+  header <lang> [<key> <value>...]: show or change the metadata of <lang> (name, source, target, author, version, description, direction or additionals)
+  check [langs]: report mistakes in [langs] (default: all) with their file and line and exit with 1 if there are any
+  dedupe <lang>: find vocabulary of <lang> with the same name and merge it after asking
 Options:
   -h,--help: print this help page and exit
   -v,--version: print the version and exit
   --config <config>: set a different config path
   --config-dir <confdir>: set a different config dir
   -l,--lang <lang>: set the lang to choose vocabulary from
-  -d,--dict <dict> <name> <meanings> [additionals]: add a new entry to an existing dict (meanings is a comma seperated list and additionals a comma seperated list of `key:value` pairs)
+  -d,--dict <dict> <name> <meanings> [additionals] [tags]: add a new entry to an existing dict (meanings and tags are comma seperated lists and additionals a comma seperated list of `key:value` pairs, a backslash escapes ';', ',' and ':')
   -V,--vocab <vocab>: sets how many vocabs should be trained (all or one)
   --noadds: disable additionals
   --adds: enable additionals
@@ -178,5 +502,25 @@ Options:
   --db: enable database
   --nodb: disable database
   -q,--query <query>: query vocabulary for <query>
+  --reverse: ask for the vocabulary of a meaning instead of the meanings of a vocabulary
+  --mixed: randomly ask for meanings or vocabulary
+  -c,--choice: ask multiple choice questions (answered with the number of a choice)
+  --loop: ask wrongly answered vocabulary again until every vocabulary was answered correctly
+  --noloop: ask every vocabulary only once
+  -n,--limit <amount>: learn at most <amount> vocabularies
+  --minutes <minutes>: end the session after <minutes> minutes
+  --select <strategy>: sets which vocabulary is learned first (random, oldest, most-missed or newest; oldest and newest use the order the vocabulary was added in)
+  -t,--tag <tags>: only learn or pretty print vocabulary with one of the comma seperated <tags>
+  --exclude-tag <tags>: don't learn or pretty print vocabulary with one of the comma seperated <tags>
+  --due: only learn vocabulary that is due for review
+  --scheduler <scheduler>: sets the algorithm scheduling reviews (sm2 or leitner)
+  --format <format>: sets the format of the output of -q, -p and the session summary (json or tsv)
+  --delimiter <format>: sets the format of imported and exported files (csv or tsv, default: file extension)
+  --columns <columns>: comma seperated list of what the columns of imported and exported files contain (name, meanings, tags, an additional key or - to skip a column)
+  --noheader: the imported file has no header row
+  --dry-run: only show what migrate or dedupe would do
+  --keep: keep the migrated languages in their old place
+  --lenient: skip invalid lines of dict files with a warning instead of failing
+  --anki <package>: import an Anki package (the fields of its notes are mapped with --columns, default: name,meanings)
 ";
 const VERSION_STR: &str = "vct: v1.5.20-nightly";
@@ -1,4 +1,5 @@
 use serde_derive::Deserialize;
+use std::collections::HashMap;
 use std::fs::read_to_string;
 use std::io::Error;
 
@@ -11,6 +12,74 @@ pub struct Config {
     pub clearlines: Option<bool>,
     pub database: Option<bool>,
     pub dbpath: Option<String>,
+    pub scheduler: Option<String>,
+    pub tolerance: Option<f32>,
+    pub learning_loop: Option<bool>,
+    pub limit: Option<usize>,
+    pub minutes: Option<u64>,
+    pub select: Option<String>,
+    pub languages: Option<HashMap<String, LangConfig>>,
+}
+
+#[derive(Deserialize, Clone)]
+/// struct representing settings specific to a language (the `[languages.<lang>]` tables)
+pub struct LangConfig {
+    pub tolerance: Option<f32>,
+    pub normalization: Option<String>,
+    pub accents: Option<String>,
+    pub locale: Option<String>,
+    pub collapse_whitespace: Option<bool>,
+    pub strip_punctuation: Option<bool>,
+}
+
+impl Config {
+    /// get the path to the database. Relative paths are put in the config directory
+    /// # Arguments
+    /// * `config_dir`: the config directory of vct
+    /// # Returns
+    /// the absolute path to the database as a `String`
+    pub fn db_path(&self, config_dir: &str) -> String {
+        let path: String = match &self.dbpath {
+            Some(n) => n.to_string(),
+            None => String::from("db.sqlite"),
+        };
+        if path.starts_with('/') {
+            path
+        } else {
+            format!("{}/{}", config_dir, path)
+        }
+    }
+}
+
+impl Config {
+    /// get the path to the directory containing the dict files. Relative paths are put in the
+    /// config directory
+    /// # Arguments
+    /// * `config_dir`: the config directory of vct
+    pub fn dict_dir(&self, config_dir: &str) -> String {
+        match &self.dict {
+            Some(n) if n.starts_with('/') => n.to_string(),
+            Some(n) if !n.is_empty() => format!("{}/{}", config_dir, n),
+            _ => format!("{}/dicts", config_dir),
+        }
+    }
+
+    /// get the settings of a language if there are any
+    pub fn lang(&self, lang: &str) -> Option<&LangConfig> {
+        match &self.languages {
+            Some(n) => n.get(lang),
+            None => None,
+        }
+    }
+
+    /// get the tolerated edits per character for answers of a language. The language specific
+    /// setting takes precedence over the global one
+    pub fn tolerance(&self, lang: &str) -> f32 {
+        match self.lang(lang).and_then(|x| x.tolerance) {
+            Some(n) => n,
+            None => self.tolerance.unwrap_or(0.0),
+        }
+    }
 }
 
 /// load the configuration file
@@ -0,0 +1,183 @@
+use crate::dict::LineError;
+use crate::header::Header;
+use crate::normalize::Normalizer;
+use crate::storage::{Entry, Storage};
+use std::collections::HashMap;
+use std::fmt;
+
+/// a problem found in the vocabulary of a language
+#[derive(Debug, Clone, PartialEq)]
+pub struct Problem {
+    /// where the problem is (e.g. the dict file and line)
+    pub location: String,
+    pub message: String,
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.location, self.message)
+    }
+}
+
+impl From<&LineError> for Problem {
+    fn from(error: &LineError) -> Problem {
+        Problem {
+            location: format!("{}:{}:{}", error.path, error.line, error.column),
+            message: error.reason.clone(),
+        }
+    }
+}
+
+/// find the lines of a dict file ending with whitespace
+/// # Arguments
+/// * `path`: the path of the file (used for the locations)
+/// * `contents`: the contents of the file
+pub fn trailing_whitespace(path: &str, contents: &str) -> Vec<Problem> {
+    contents
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| line.trim_end() != *line)
+        .map(|(idx, line)| Problem {
+            location: format!(
+                "{}:{}:{}",
+                path,
+                idx + 1,
+                line.trim_end().chars().count() + 1
+            ),
+            message: String::from("trailing whitespace"),
+        })
+        .collect()
+}
+
+/// find the values of vocabulary ending with whitespace (dict files are checked line by line with
+/// `trailing_whitespace` instead)
+/// # Arguments
+/// * `storage`: the storage containing the language
+/// * `lang`: the language
+/// * `entries`: its vocabulary
+pub fn whitespace<S: Storage + ?Sized>(storage: &S, lang: &str, entries: &[Entry]) -> Vec<Problem> {
+    let mut out: Vec<Problem> = Vec::new();
+    for entry in entries {
+        let voc = &entry.vocab;
+        let values: Vec<String> = std::iter::once(voc.get_name())
+            .chain(voc.get_meanings())
+            .chain(voc.get_additionals().unwrap_or_default())
+            .chain(voc.get_tags())
+            .collect();
+        for value in values.iter().filter(|x| x.trim_end() != x.as_str()) {
+            out.push(Problem {
+                location: storage.location(lang, entry.id),
+                message: format!("trailing whitespace in '{}'", value),
+            });
+        }
+    }
+    out
+}
+
+/// check the vocabulary of a language for mistakes that don't stop it from being loaded
+/// # Arguments
+/// * `storage`: the storage containing the language (used for the locations)
+/// * `lang`: the language
+/// * `entries`: its vocabulary
+/// * `header`: its header. If it declares `additionals` every other key is reported
+/// * `normalizer`: how names and meanings are compared
+pub fn check_entries<S: Storage + ?Sized>(
+    storage: &S,
+    lang: &str,
+    entries: &[Entry],
+    header: &Header,
+    normalizer: &Normalizer,
+) -> Vec<Problem> {
+    // the spelling of an additional key used most often is the expected one
+    let mut spellings: HashMap<String, HashMap<String, usize>> = HashMap::new();
+    for entry in entries {
+        for add in entry.vocab.get_additionals().unwrap_or_default() {
+            if let Some((key, _)) = add.split_once(':') {
+                *spellings
+                    .entry(key.trim().to_lowercase())
+                    .or_default()
+                    .entry(key.to_string())
+                    .or_default() += 1;
+            }
+        }
+    }
+    let expected = |key: &str| -> String {
+        spellings
+            .get(&key.trim().to_lowercase())
+            .and_then(|x| x.iter().max_by(|a, b| a.1.cmp(b.1).then(b.0.cmp(a.0))))
+            .map(|(spelling, _)| spelling.clone())
+            .unwrap_or(key.to_string())
+    };
+
+    let mut out: Vec<Problem> = Vec::new();
+    let mut names: HashMap<String, i64> = HashMap::new();
+    for entry in entries {
+        let voc = &entry.vocab;
+        let mut problem = |message: String| {
+            out.push(Problem {
+                location: storage.location(lang, entry.id),
+                message,
+            })
+        };
+
+        let name: String = normalizer.normalize(voc.get_name().as_str());
+        match names.get(&name) {
+            Some(n) => problem(format!(
+                "duplicate name '{}' (first at {})",
+                voc.get_name(),
+                storage.location(lang, *n)
+            )),
+            None => {
+                names.insert(name, entry.id);
+            }
+        }
+
+        let meanings: Vec<String> = voc
+            .get_meanings()
+            .iter()
+            .map(|x| normalizer.normalize(x))
+            .collect();
+        if meanings.iter().all(|x| x.is_empty()) {
+            problem(String::from("no meanings"));
+        } else if meanings.iter().any(|x| x.is_empty()) {
+            problem(String::from("empty meaning"));
+        }
+        for (idx, meaning) in meanings.iter().enumerate() {
+            if !meaning.is_empty() && meanings[..idx].contains(meaning) {
+                problem(format!(
+                    "duplicate meaning '{}'",
+                    voc.get_meanings()[idx].trim()
+                ));
+            }
+        }
+
+        for (idx, add) in voc.get_additionals().unwrap_or_default().iter().enumerate() {
+            let (key, value) = match add.split_once(':') {
+                Some((key, value)) if !key.trim().is_empty() => (key, value),
+                _ => {
+                    problem(format!("additional {} isn't a key:value pair", idx + 1));
+                    continue;
+                }
+            };
+            if value.trim().is_empty() {
+                problem(format!("additional '{}' has no value", key));
+            }
+            if !header.additionals.is_empty() {
+                if !header.additionals.iter().any(|x| x == key) {
+                    problem(format!(
+                        "additional key '{}' isn't declared in the header (expected {})",
+                        key,
+                        header.additionals.join(", ")
+                    ));
+                }
+            } else if expected(key) != key {
+                problem(format!(
+                    "additional key '{}' is written '{}' elsewhere",
+                    key,
+                    expected(key)
+                ));
+            }
+        }
+    }
+    out
+}
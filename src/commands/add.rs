@@ -0,0 +1,27 @@
+use crate::args::Params;
+use crate::cfg::Config;
+use crate::commands::open_storage;
+use crate::dict::Vocab;
+use crate::error::*;
+use btui::Terminal;
+
+/// add the vocab given with `-d` to a language
+pub fn run(params: &Params, conf: &Config, _term: &Terminal) -> Result<(), VctError> {
+    // the arguments are escaped like a line of a dict file
+    let (dict_fname, line) = match params.dict.split_once(';') {
+        Some(n) => n,
+        None => {
+            return Err(VctError::new(
+                VctErrorKind::ParamError,
+                "-d needs a dict and a vocab in the form `<dict> <name> <meanings> [additionals] [tags]`, e.g. `vct -d german hund dog`",
+            )
+            .context("error parsing vocabulary"));
+        }
+    };
+    let vocab: Vocab =
+        Vocab::from_string(line.to_string()).map_err(|e| e.context("error parsing vocabulary"))?;
+    open_storage(params, conf)?
+        .insert(dict_fname, &vocab)
+        .map_err(|e| e.context("problems writing vocab"))?;
+    Ok(())
+}
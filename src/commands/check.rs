@@ -0,0 +1,64 @@
+use crate::args::Params;
+use crate::cfg::Config;
+use crate::commands::open_storage;
+use crate::error::*;
+use crate::info;
+use crate::normalize::Normalizer;
+use crate::storage::Storage;
+use btui::Terminal;
+
+/// report mistakes in the given languages (or all of them). Finding any problem makes the command
+/// fail
+pub fn run(params: &Params, conf: &Config, term: &Terminal) -> Result<(), VctError> {
+    let storage: Box<dyn Storage> = open_storage(params, conf)?;
+    let langs: Vec<String> = if params.command_args.is_empty() {
+        storage
+            .languages()
+            .map_err(|e| e.context("error listing languages"))?
+    } else {
+        params.command_args.clone()
+    };
+    let mut problems: usize = 0;
+    let mut failed: usize = 0;
+    for lang in &langs {
+        let normalizer = Normalizer::from_config(conf, lang.as_str());
+        match storage.check(lang.as_str(), &normalizer) {
+            Ok(n) => {
+                for problem in &n {
+                    term.println(format!("{}", problem)).unwrap();
+                }
+                problems += n.len();
+            }
+            Err(e) => {
+                info::print_info(
+                    term,
+                    format!("error checking {}: {}", lang, e),
+                    info::MessageType::Error,
+                );
+                failed += 1;
+            }
+        }
+    }
+    if problems == 0 && failed == 0 {
+        term.println(format!(
+            "checked {} languages, no problems found",
+            langs.len()
+        ))
+        .unwrap();
+        return Ok(());
+    }
+    Err(VctError::new(
+        VctErrorKind::ParsingError,
+        format!(
+            "checked {} languages, found {} problems{}",
+            langs.len(),
+            problems,
+            if failed == 0 {
+                String::new()
+            } else {
+                format!(" and couldn't check {}", failed)
+            }
+        )
+        .as_str(),
+    ))
+}
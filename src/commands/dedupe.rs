@@ -0,0 +1,77 @@
+use crate::args::Params;
+use crate::cfg::Config;
+use crate::commands::{arg, open_storage, update_review};
+use crate::dedupe::{self, Decision, Group};
+use crate::dict::Vocab;
+use crate::error::*;
+use crate::normalize::Normalizer;
+use crate::storage::{Entry, Storage};
+use btui::Terminal;
+
+/// find the vocabulary of a language with the same name and merge it after asking
+pub fn run(params: &Params, conf: &Config, term: &Terminal) -> Result<(), VctError> {
+    let lang: &str = arg(params, 0, "vct dedupe <lang>")?;
+    let mut storage: Box<dyn Storage> = open_storage(params, conf)?;
+    let normalizer = Normalizer::from_config(conf, lang);
+    let entries: Vec<Entry> = storage
+        .load(lang)
+        .map_err(|e| e.context(format!("error reading {}", lang).as_str()))?;
+    let groups: Vec<Group> = dedupe::find(&entries, &normalizer);
+    if groups.is_empty() {
+        term.println(format!("{} has no duplicates", lang)).unwrap();
+        return Ok(());
+    }
+    let mut merges: Vec<(Group, Vocab)> = Vec::new();
+    for (idx, mut group) in groups.iter().cloned().enumerate() {
+        term.println(format!(
+            "\n({}/{}) '{}' is stored {} times ({})",
+            idx + 1,
+            groups.len(),
+            group.entries[0].vocab.get_name(),
+            group.entries.len(),
+            group
+                .entries
+                .iter()
+                .map(|x| storage.location(lang, x.id))
+                .collect::<Vec<String>>()
+                .join(", ")
+        ))
+        .unwrap();
+        term.print(group.table(&normalizer)).unwrap();
+        if params.dry_run {
+            continue;
+        }
+        match dedupe::ask(term, &mut group)? {
+            Decision::Merge => {
+                let merged: Vocab = group.merged(&normalizer);
+                merges.push((group, merged));
+            }
+            Decision::Skip => (),
+            Decision::Quit => break,
+        }
+    }
+    if params.dry_run || merges.is_empty() {
+        return Ok(());
+    }
+    dedupe::apply(&mut *storage, lang, &merges)
+        .map_err(|e| e.context("error merging duplicates"))?;
+    for (group, merged) in &merges {
+        for entry in &group.entries[1..] {
+            update_review(
+                &*storage,
+                conf.db_path(params.config_dir.as_str()).as_str(),
+                lang,
+                entry.vocab.get_name().as_str(),
+                Some((lang, merged.get_name().as_str())),
+                term,
+            );
+        }
+    }
+    term.println(format!(
+        "\nmerged {} vocabularies into {}",
+        merges.iter().map(|(x, _)| x.entries.len()).sum::<usize>(),
+        merges.len()
+    ))
+    .unwrap();
+    Ok(())
+}
@@ -0,0 +1,66 @@
+use crate::args::Params;
+use crate::cfg::Config;
+use crate::commands::{arg, open_storage, update_review};
+use crate::dict::Vocab;
+use crate::edit;
+use crate::error::*;
+use crate::storage::{Entry, Storage};
+use btui::Terminal;
+
+/// change (`edit`), remove (`rm`) or move (`mv`) a vocab and show it before and after the change
+pub fn run(params: &Params, conf: &Config, term: &Terminal) -> Result<(), VctError> {
+    let command: &str = params.command.as_deref().unwrap_or_default();
+    let usage: String = format!("vct {} <lang> <vocab> ...", command);
+    let lang: &str = arg(params, 0, usage.as_str())?;
+    let key: &str = arg(params, 1, usage.as_str())?;
+    let mut storage: Box<dyn Storage> = open_storage(params, conf)?;
+    let entry: Entry = edit::find(&*storage, lang, key)?;
+    let name: String = entry.vocab.get_name();
+    // the language, id and vocab after the change (`None` if it was removed)
+    let after: Option<(String, i64, Vocab)> = match command {
+        "edit" => edit::set_fields(&entry.vocab, &params.command_args[2..]).and_then(|x| {
+            storage.update(lang, entry.id, &x)?;
+            Ok(Some((lang.to_string(), entry.id, x)))
+        }),
+        "mv" => match params.command_args.get(2) {
+            Some(n) if n == lang => Err(VctError::new(
+                VctErrorKind::ParamError,
+                format!("'{}' already is in {}", name, lang).as_str(),
+            )),
+            // insert first so the vocab isn't lost if writing fails
+            Some(n) => storage.insert(n.as_str(), &entry.vocab).and_then(|id| {
+                storage.delete(lang, entry.id)?;
+                Ok(Some((n.clone(), id, entry.vocab.clone())))
+            }),
+            None => Err(VctError::new(
+                VctErrorKind::ParamError,
+                "no language to move to provided",
+            )),
+        },
+        _ => storage.delete(lang, entry.id).map(|_| None),
+    }
+    .map_err(|e| e.context(format!("error changing '{}'", name).as_str()))?;
+    let target: Option<(String, String)> =
+        after.as_ref().map(|(l, _, v)| (l.clone(), v.get_name()));
+    update_review(
+        &*storage,
+        conf.db_path(params.config_dir.as_str()).as_str(),
+        lang,
+        name.as_str(),
+        target.as_ref().map(|(l, n)| (l.as_str(), n.as_str())),
+        term,
+    );
+    let before_label: String = storage.location(lang, entry.id);
+    let after_label: Option<String> = after
+        .as_ref()
+        .map(|(l, id, _)| storage.location(l.as_str(), *id));
+    term.println(edit::confirmation(
+        Some((before_label.as_str(), &entry.vocab)),
+        after
+            .as_ref()
+            .zip(after_label.as_ref())
+            .map(|((_, _, v), label)| (label.as_str(), v)),
+    ))
+    .unwrap();
+    Ok(())
+}
@@ -0,0 +1,25 @@
+use crate::args::Params;
+use crate::cfg::Config;
+use crate::commands::{arg, open_storage, update_review};
+use crate::editor;
+use crate::error::*;
+use crate::storage::Storage;
+use btui::Terminal;
+
+/// browse, filter and change the vocabulary of a language in the full screen editor
+pub fn run(params: &Params, conf: &Config, term: &Terminal) -> Result<(), VctError> {
+    let lang: &str = arg(params, 0, "vct editor <lang>")?;
+    let mut storage: Box<dyn Storage> = open_storage(params, conf)?;
+    let changes: Vec<(String, Option<String>)> = editor::run(&mut *storage, lang, term)?;
+    for (name, new_name) in &changes {
+        update_review(
+            &*storage,
+            conf.db_path(params.config_dir.as_str()).as_str(),
+            lang,
+            name.as_str(),
+            new_name.as_ref().map(|x| (lang, x.as_str())),
+            term,
+        );
+    }
+    Ok(())
+}
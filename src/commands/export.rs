@@ -0,0 +1,32 @@
+use crate::args::Params;
+use crate::cfg::Config;
+use crate::commands::{arg, open_storage};
+use crate::csvfile::{self, Column};
+use crate::dict::Vocab;
+use crate::error::*;
+use btui::Terminal;
+
+/// write the vocabulary of a language to a CSV/TSV file
+pub fn run(params: &Params, conf: &Config, term: &Terminal) -> Result<(), VctError> {
+    const USAGE: &str = "vct export <lang> <file>";
+    let lang: &str = arg(params, 0, USAGE)?;
+    let path: &str = arg(params, 1, USAGE)?;
+    let vocab: Vec<Vocab> = open_storage(params, conf)?
+        .load(lang)
+        .map_err(|e| e.context("error while parsing vocabulary dictionary"))?
+        .into_iter()
+        .map(|x| x.vocab)
+        .collect();
+    csvfile::delimiter(path, params.delimiter.as_deref())
+        .and_then(|d| {
+            let columns: Vec<Column> = match &params.columns {
+                Some(n) => csvfile::parse_columns(n.as_str())?,
+                None => csvfile::default_columns(&vocab),
+            };
+            csvfile::export(path, d, &columns, &vocab)
+        })
+        .map_err(|e| e.context("error exporting vocabulary"))?;
+    term.println(format!("exported {} vocabulary to {}", vocab.len(), path))
+        .unwrap();
+    Ok(())
+}
@@ -0,0 +1,41 @@
+use crate::args::Params;
+use crate::cfg::Config;
+use crate::commands::{arg, open_storage};
+use crate::error::*;
+use crate::header::Header;
+use crate::pretty_print;
+use crate::storage::Storage;
+use btui::Terminal;
+
+/// show the metadata of a language or change it with pairs of a key and its new value
+pub fn run(params: &Params, conf: &Config, term: &Terminal) -> Result<(), VctError> {
+    let lang: &str = arg(params, 0, "vct header <lang> [<key> <value>...]")?;
+    let mut storage: Box<dyn Storage> = open_storage(params, conf)?;
+    let mut header: Header = storage
+        .header(lang)
+        .map_err(|e| e.context(format!("error while reading the header of {}", lang).as_str()))?;
+    let changes: &[String] = &params.command_args[1..];
+    if changes.is_empty() {
+        for (key, value) in header.pairs() {
+            term.println(format!("{}: {}", key, value)).unwrap();
+        }
+        return Ok(());
+    }
+    if !changes.len().is_multiple_of(2) {
+        return Err(VctError::new(
+            VctErrorKind::ParamError,
+            "expected pairs of a key and its new value",
+        ));
+    }
+    for pair in changes.chunks(2) {
+        if let Err(e) = header.set(pair[0].as_str(), pair[1].as_str()) {
+            return Err(VctError::new(VctErrorKind::ParamError, e.as_str()));
+        }
+    }
+    storage
+        .set_header(lang, &header)
+        .map_err(|e| e.context(format!("error writing the header of {}", lang).as_str()))?;
+    term.print(pretty_print::pretty_header(lang, &header))
+        .unwrap();
+    Ok(())
+}
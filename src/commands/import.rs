@@ -0,0 +1,92 @@
+use crate::anki;
+use crate::args::Params;
+use crate::cfg::Config;
+use crate::commands::open_storage;
+use crate::csvfile::{self, Column};
+use crate::dict::Vocab;
+use crate::error::*;
+use crate::info;
+use crate::storage::Storage;
+use btui::Terminal;
+
+/// add the vocabulary of a CSV/TSV file or an Anki package (`--anki`) to the language set with
+/// `-l`. Rows or notes that can't be imported are reported and make the import fail after the
+/// others were added
+pub fn run(params: &Params, conf: &Config, term: &Terminal) -> Result<(), VctError> {
+    let path: &str = match params.anki.as_ref().or(params.command_args.first()) {
+        Some(n) => n.as_str(),
+        None => {
+            return Err(VctError::new(
+                VctErrorKind::ParamError,
+                "no file to import provided",
+            ));
+        }
+    };
+    if params.lang.is_empty() {
+        return Err(VctError::new(
+            VctErrorKind::ParamError,
+            "no lang to import into provided (use `-l <lang>`)",
+        ));
+    }
+    let mut storage: Box<dyn Storage> = open_storage(params, conf)?;
+    let columns: Option<Vec<Column>> = match &params.columns {
+        Some(n) => Some(
+            csvfile::parse_columns(n.as_str())
+                .map_err(|e| e.context("error importing vocabulary"))?,
+        ),
+        None => None,
+    };
+    // the vocabulary and a report line for every row or note that wasn't imported
+    let (vocab, rejected): (Vec<Vocab>, Vec<String>) = if params.anki.is_some() {
+        let columns: Vec<Column> = columns.unwrap_or_else(|| vec![Column::Name, Column::Meanings]);
+        anki::import(path, &columns).map(|(vocab, unmapped)| {
+            let report: Vec<String> = unmapped
+                .iter()
+                .map(|x| format!("note {} ({}): {}", x.id, x.first, x.reason))
+                .collect();
+            (vocab, report)
+        })
+    } else {
+        csvfile::delimiter(path, params.delimiter.as_deref())
+            .and_then(|d| csvfile::import(path, d, columns, params.header))
+            .map(|(vocab, rejected)| {
+                let report: Vec<String> = rejected
+                    .iter()
+                    .map(|x| format!("{}:{}: {}", path, x.line, x.reason))
+                    .collect();
+                (vocab, report)
+            })
+    }
+    .map_err(|e| e.context("error importing vocabulary"))?;
+    for voc in &vocab {
+        storage
+            .insert(params.lang.as_str(), voc)
+            .map_err(|e| e.context("problems writing vocab"))?;
+    }
+    for row in &rejected {
+        info::print_info(term, row, info::MessageType::Warning);
+    }
+    // what was rejected: rows of a CSV/TSV file or notes of an Anki package
+    let noun: &str = match (params.anki.is_some(), rejected.len()) {
+        (true, 1) => "note",
+        (true, _) => "notes",
+        (false, 1) => "row",
+        (false, _) => "rows",
+    };
+    term.println(format!(
+        "imported {} vocabulary into {}, rejected {} {}",
+        vocab.len(),
+        params.lang,
+        rejected.len(),
+        noun
+    ))
+    .unwrap();
+    if rejected.is_empty() {
+        Ok(())
+    } else {
+        Err(VctError::new(
+            VctErrorKind::ParsingError,
+            format!("{} {} couldn't be imported", rejected.len(), noun).as_str(),
+        ))
+    }
+}
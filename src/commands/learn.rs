@@ -0,0 +1,261 @@
+use crate::args::Params;
+use crate::cfg::Config;
+use crate::commands::open_storage;
+use crate::dict::Vocab;
+use crate::error::*;
+use crate::header::Header;
+use crate::history::History;
+use crate::info;
+use crate::normalize::Normalizer;
+use crate::output::{self, Format};
+use crate::question::{self, Direction, Prompt, QuestionOptions, SessionReport, SessionResult};
+use crate::review::{self, ReviewStore, Scheduler};
+use crate::select::{self, Strategy};
+use crate::storage::{Entry, Storage};
+use btui::pbar::ExtProgressBar;
+use btui::Terminal;
+use std::collections::HashMap;
+
+/// format the amount of almost correct answers for the session summary
+fn almost_str(almost: usize) -> String {
+    if almost == 0 {
+        String::new()
+    } else {
+        format!(" and {} almost correct", almost)
+    }
+}
+
+/// learn the vocabulary of the language set with `-l`, save the review state and the session
+/// history and show a summary
+pub fn run(params: &Params, conf: &Config, term: &Terminal) -> Result<(), VctError> {
+    let lang: &str = params.lang.as_str();
+    let format: Format = Format::from_name(params.format.as_deref())?;
+    let storage: Box<dyn Storage> = open_storage(params, conf)?;
+    let mut entries: Vec<Entry> = storage
+        .load(lang)
+        .map_err(|e| e.context("error while parsing vocabulary dictionary"))?;
+    // the ids are in insertion order, which the oldest and newest strategies rely on
+    entries.sort_by_key(|x| x.id);
+    let vocab: Vec<Vocab> = entries.into_iter().map(|x| x.vocab).collect();
+    let header: Header = storage
+        .header(lang)
+        .map_err(|e| e.context(format!("error while reading the header of {}", lang).as_str()))?;
+    let db_path: String = conf.db_path(params.config_dir.as_str());
+    let scheduler: Scheduler = match params.scheduler.as_ref().or(conf.scheduler.as_ref()) {
+        Some(n) => match Scheduler::from_name(n.as_str()) {
+            Some(s) => s,
+            None => {
+                info::print_info(
+                    term,
+                    format!("'{}' is not a valid scheduler. Using sm2", n),
+                    info::MessageType::Warning,
+                );
+                Scheduler::Sm2
+            }
+        },
+        None => Scheduler::Sm2,
+    };
+    let store: Option<ReviewStore> = match ReviewStore::open(db_path.as_str(), scheduler) {
+        Ok(n) => Some(n),
+        Err(e) if params.due => return Err(e.context("error opening review state")),
+        Err(e) => {
+            info::print_info(
+                term,
+                format!("review state won't be saved: {}", e),
+                info::MessageType::Warning,
+            );
+            None
+        }
+    };
+    let vocab: Vec<Vocab> = select::filter_tags(vocab, &params.tags, &params.exclude_tags);
+    let vocab: Vec<Vocab> = match &store {
+        Some(n) if params.due => n
+            .due(lang, vocab)
+            .map_err(|e| e.context("error filtering due vocabulary"))?,
+        _ => vocab,
+    };
+    let strategy: Strategy = match params.select.as_ref().or(conf.select.as_ref()) {
+        Some(n) => match Strategy::from_name(n.as_str()) {
+            Some(s) => s,
+            None => {
+                info::print_info(
+                    term,
+                    format!("'{}' is not a valid selection strategy. Using random", n),
+                    info::MessageType::Warning,
+                );
+                Strategy::Random
+            }
+        },
+        None => Strategy::Random,
+    };
+    let missed: HashMap<String, usize> = if strategy == Strategy::MostMissed {
+        History::open(db_path.as_str())
+            .and_then(|x| x.missed(lang))
+            .map_err(|e| e.context("error reading session history"))?
+    } else {
+        HashMap::new()
+    };
+    let vocab: Vec<Vocab> = select::select(vocab, strategy, params.limit.or(conf.limit), &missed);
+    if vocab.is_empty() {
+        if params.due {
+            term.println(format!("no {} vocabulary is due for review", lang))
+                .unwrap();
+        } else {
+            term.println(format!("no {} vocabulary to learn", lang))
+                .unwrap();
+        }
+        return Ok(());
+    }
+    let amount: String = match params.vocab.as_str() {
+        "all" => String::from("all"),
+        "one" => String::from("one"),
+        _ => match &conf.vocab {
+            Some(n) => match n.as_str() {
+                "all" => String::from("all"),
+                "one" => String::from("one"),
+                _ => String::from("one"),
+            },
+            None => String::from("one"),
+        },
+    };
+    let adds: bool = match params.adds {
+        Some(n) => n,
+        None => conf.additionals.unwrap_or(true),
+    };
+    let clearlines: bool = match params.clearlines {
+        Some(n) => n,
+        None => conf.clearlines.unwrap_or(false),
+    };
+    let learning_loop: bool = match params.learning_loop {
+        Some(n) => n,
+        None => conf.learning_loop.unwrap_or(false),
+    };
+    let started: i64 = review::now();
+    let options: QuestionOptions = QuestionOptions {
+        amount,
+        adds,
+        clearlines,
+        tolerance: conf.tolerance(lang),
+        normalizer: Normalizer::from_config(conf, lang),
+        direction: match params.direction.as_deref() {
+            Some("reverse") => Direction::Reverse,
+            Some("mixed") => Direction::Mixed,
+            _ => header.direction.unwrap_or(Direction::Forward),
+        },
+        title: header.title(lang),
+        choice: params.choice,
+        learning_loop,
+        shuffle: strategy == Strategy::Random,
+        minutes: params.minutes.or(conf.minutes),
+        stderr: format != Format::Pretty,
+    };
+    let session: SessionResult = question::question_vocab(lang.to_string(), vocab, &options);
+    if let Some(n) = &store {
+        if let Err(e) = n.record(lang, &session.cards) {
+            info::print_info(
+                term,
+                format!("error saving review state: {}", e),
+                info::MessageType::Warning,
+            );
+        }
+    }
+
+    match History::open(db_path.as_str()) {
+        Ok(mut n) => {
+            if let Err(e) = n.record(lang, started, review::now(), &session) {
+                info::print_info(
+                    term,
+                    format!("error saving session history: {}", e),
+                    info::MessageType::Warning,
+                );
+            }
+        }
+        Err(e) => {
+            info::print_info(
+                term,
+                format!("session history won't be saved: {}", e),
+                info::MessageType::Warning,
+            );
+        }
+    }
+
+    // if clearlines is enabled clear the line above
+    if clearlines {
+        let prompt: Prompt = Prompt::new(options.stderr);
+        prompt.move_cursor(-1).unwrap();
+        prompt.clear_line().unwrap();
+        prompt.set_cursor_x(1).unwrap();
+    }
+
+    if session.cards.is_empty() {
+        return Ok(());
+    }
+    if format != Format::Pretty {
+        let report: SessionReport = SessionReport::new(lang, &session, adds);
+        term.print(output::session(&report, format)?).unwrap();
+        return Ok(());
+    }
+
+    // almost correct answers count half
+    let result: f32 = session.correct as f32;
+    let vocab_total: f32 = session.cards.len() as f32;
+    let total: f32 = ((result + session.almost as f32 * 0.5) / vocab_total) * 100.0;
+    let mut norm_bar = ExtProgressBar::new("[=> ]", "result");
+    norm_bar.set_progress(total);
+    if learning_loop {
+        term.println(format!(
+            "\nyou had {} out of {} correct on the first try{}",
+            result,
+            vocab_total,
+            almost_str(session.almost)
+        ))
+        .unwrap();
+        // the queue is only left over if the time was up
+        if session.unanswered == 0 {
+            term.println(format!(
+                "you had all {} correct after {} retries",
+                vocab_total, session.retries
+            ))
+            .unwrap();
+        } else {
+            term.println(format!(
+                "time ran out with {} vocabularies not answered correctly yet after {} retries",
+                session.unanswered, session.retries
+            ))
+            .unwrap();
+        }
+    } else {
+        term.println(format!(
+            "\nyou had {} out of {} correct{}",
+            result,
+            vocab_total,
+            almost_str(session.almost)
+        ))
+        .unwrap();
+    }
+    term.println(format!("{}\n", norm_bar.render())).unwrap();
+
+    let add_result: f32 = session.add_correct as f32;
+    let mut add_total: f32 = 0.0;
+    for i in &session.cards {
+        if let Some(n) = i.vocab.get_additionals() {
+            add_total += n.len() as f32;
+        }
+    }
+    // there is no score without additionals
+    if !adds || add_total == 0.0 {
+        return Ok(());
+    }
+    let add_score: f32 = ((add_result + session.add_almost as f32 * 0.5) / add_total) * 100.0;
+    let mut add_bar = ExtProgressBar::new("[=> ]", "result");
+    add_bar.set_progress(add_score);
+    term.println(format!(
+        "\n(additional) you had {} out of {} correct{}",
+        add_result,
+        add_total,
+        almost_str(session.add_almost)
+    ))
+    .unwrap();
+    term.println(format!("\n{}", add_bar.render())).unwrap();
+    Ok(())
+}
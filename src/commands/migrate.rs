@@ -0,0 +1,149 @@
+use crate::args::Params;
+use crate::cfg::Config;
+use crate::error::*;
+use crate::info;
+use crate::migrate::{self, Plan};
+use crate::storage::{self, MemoryStorage, Storage};
+use btui::Terminal;
+
+/// move languages from the dict files into the database (`to-db`) or back (`to-dict`). Languages
+/// that fail are reported and make the command fail after the others were migrated
+pub fn run(params: &Params, conf: &Config, term: &Terminal) -> Result<(), VctError> {
+    let to_db: bool = match params.command_args.first().map(|x| x.as_str()) {
+        Some("to-db") => true,
+        Some("to-dict") => false,
+        _ => {
+            return Err(VctError::new(
+                VctErrorKind::ParamError,
+                "usage: vct migrate <to-db|to-dict> [langs]",
+            ));
+        }
+    };
+    let (from, to) = if to_db {
+        ("dict files", "database")
+    } else {
+        ("database", "dict files")
+    };
+    let open = |db: bool| {
+        storage::open(params.config_dir.as_str(), conf, db, params.lenient)
+            .map_err(|e| e.context("error opening vocabulary"))
+    };
+    let mut source: Box<dyn Storage> = open(!to_db)?;
+    let mut target: Box<dyn Storage> = open(to_db)?;
+    let langs: Vec<String> = if params.command_args.len() > 1 {
+        params.command_args[1..].to_vec()
+    } else {
+        source
+            .languages()
+            .map_err(|e| e.context("error listing languages"))?
+    };
+    let mut failed: Vec<String> = Vec::new();
+    for lang in langs {
+        let plan: Plan = match migrate::plan(&*source, &*target, lang.as_str()) {
+            Ok(n) => n,
+            Err(e) => {
+                info::print_info(
+                    term,
+                    format!("error reading {}: {}", lang, e),
+                    info::MessageType::Error,
+                );
+                failed.push(lang);
+                continue;
+            }
+        };
+        term.println(format!(
+            "{}: {} to migrate from the {} into the {}, {} already present, {} repeated, {} conflicting",
+            lang,
+            plan.vocab.len(),
+            from,
+            to,
+            plan.present.len(),
+            plan.repeated.len(),
+            plan.conflicts.len()
+        ))
+        .unwrap();
+        // every vocab that isn't written is listed since the source may be removed afterwards
+        for voc in &plan.present {
+            term.println(format!(
+                "  skipping '{}': already in the {}",
+                voc.to_line(),
+                to
+            ))
+            .unwrap();
+        }
+        for voc in &plan.repeated {
+            term.println(format!(
+                "  skipping '{}': it is in the {} more than once and only migrated once",
+                voc.to_line(),
+                from
+            ))
+            .unwrap();
+        }
+        for (ours, theirs) in &plan.conflicts {
+            info::print_info(
+                term,
+                format!(
+                    "{}: '{}' differs in the {} ({})",
+                    lang,
+                    ours.to_line(),
+                    to,
+                    theirs.to_line()
+                ),
+                info::MessageType::Warning,
+            );
+        }
+        // a dry run migrates into a copy of the target kept in memory
+        let applied = if params.dry_run {
+            MemoryStorage::copy_of(&*target, std::slice::from_ref(&lang))
+                .and_then(|mut x| migrate::apply(&mut x, &plan))
+        } else {
+            migrate::apply(&mut *target, &plan)
+        };
+        match applied {
+            Ok(n) if n.is_empty() => (),
+            Ok(n) => {
+                for voc in n {
+                    info::print_info(
+                        term,
+                        format!("{}: '{}' was lost while migrating", lang, voc.to_line()),
+                        info::MessageType::Error,
+                    );
+                }
+                failed.push(lang);
+                continue;
+            }
+            Err(e) => {
+                info::print_info(
+                    term,
+                    format!("error migrating {}: {}", lang, e),
+                    info::MessageType::Error,
+                );
+                failed.push(lang);
+                continue;
+            }
+        }
+        if params.keep || params.dry_run {
+            continue;
+        }
+        if !plan.complete() {
+            info::print_info(
+                term,
+                format!("{}: keeping it in the {} because of conflicts", lang, from),
+                info::MessageType::Warning,
+            );
+            continue;
+        }
+        if let Err(e) = source.remove_language(lang.as_str()) {
+            info::print_info(term, format!("{}", e), info::MessageType::Error);
+            failed.push(lang);
+        }
+    }
+    if failed.is_empty() {
+        Ok(())
+    } else {
+        Err(VctError::new(
+            VctErrorKind::DatabaseError,
+            format!("{} couldn't be migrated", failed.join(", ")).as_str(),
+        ))
+    }
+}
@@ -0,0 +1,92 @@
+use crate::args::Params;
+use crate::cfg::Config;
+use crate::error::*;
+use crate::info;
+use crate::review;
+use crate::storage::{self, Storage};
+use btui::Terminal;
+
+pub mod add;
+pub mod check;
+pub mod dedupe;
+pub mod edit;
+pub mod editor;
+pub mod export;
+pub mod header;
+pub mod import;
+pub mod learn;
+pub mod migrate;
+pub mod pretty;
+pub mod query;
+pub mod stats;
+
+/// a command of vct. It gets the parameters, the config and the terminal and returns an error if
+/// it failed (which makes vct exit with 1)
+pub type Command = fn(&Params, &Config, &Terminal) -> Result<(), VctError>;
+
+/// open the storage chosen with `--db`/`--nodb` or in the config
+/// # Arguments
+/// * `params`: the parameters
+/// * `conf`: the config
+pub fn open_storage(params: &Params, conf: &Config) -> Result<Box<dyn Storage>, VctError> {
+    let usedb: bool = match params.usedb {
+        Some(n) => n,
+        None => conf.database.unwrap_or(false),
+    };
+    storage::open(params.config_dir.as_str(), conf, usedb, params.lenient)
+        .map_err(|e| e.context("error opening vocabulary"))
+}
+
+/// get an argument of the command
+/// # Arguments
+/// * `params`: the parameters
+/// * `idx`: the position of the argument after the command
+/// * `usage`: the usage of the command shown if the argument is missing
+pub fn arg<'a>(params: &'a Params, idx: usize, usage: &str) -> Result<&'a str, VctError> {
+    match params.command_args.get(idx) {
+        Some(n) => Ok(n.as_str()),
+        None => Err(VctError::new(
+            VctErrorKind::ParamError,
+            format!("usage: {}", usage).as_str(),
+        )),
+    }
+}
+
+/// move (or remove if `target` is `None`) the review state of a vocab that was renamed, moved or
+/// removed. The state is kept if another vocab of the language has the same name
+/// # Arguments
+/// * `storage`: the storage containing the language
+/// * `db_path`: the path to the database keeping the review state
+/// * `lang`: the language of the vocab
+/// * `name`: the name of the vocab before the change
+/// * `target`: the language and name after the change
+/// * `term`: the terminal (used to warn if the state can't be updated)
+pub fn update_review(
+    storage: &dyn Storage,
+    db_path: &str,
+    lang: &str,
+    name: &str,
+    target: Option<(&str, &str)>,
+    term: &Terminal,
+) {
+    if target == Some((lang, name)) {
+        return;
+    }
+    if let Ok(n) = storage.load(lang) {
+        if n.iter().any(|x| x.vocab.get_name() == name) {
+            return;
+        }
+    }
+    let updated =
+        review::ReviewStore::open(db_path, review::Scheduler::Sm2).and_then(|x| match target {
+            Some((new_lang, new_name)) => x.rename(lang, name, new_lang, new_name),
+            None => x.forget(lang, name),
+        });
+    if let Err(e) = updated {
+        info::print_info(
+            term,
+            format!("review state wasn't updated: {}", e),
+            info::MessageType::Warning,
+        );
+    }
+}
@@ -0,0 +1,39 @@
+use crate::args::Params;
+use crate::cfg::Config;
+use crate::commands::open_storage;
+use crate::dict::Vocab;
+use crate::error::*;
+use crate::header::Header;
+use crate::output::{self, Format};
+use crate::pretty_print;
+use crate::select;
+use crate::storage::Storage;
+use btui::Terminal;
+
+/// print the vocabulary of the language given with `-p` (filtered by its tags)
+pub fn run(params: &Params, conf: &Config, term: &Terminal) -> Result<(), VctError> {
+    let lang: &str = params.pretprin.as_deref().unwrap_or_default();
+    let format: Format = Format::from_name(params.format.as_deref())?;
+    let storage: Box<dyn Storage> = open_storage(params, conf)?;
+    let voc: Vec<Vocab> = storage
+        .load(lang)
+        .map_err(|e| e.context("error while parsing vocabulary dictionary"))?
+        .into_iter()
+        .map(|x| x.vocab)
+        .collect();
+    let voc: Vec<Vocab> = select::filter_tags(voc, &params.tags, &params.exclude_tags);
+    let header: Header = storage
+        .header(lang)
+        .map_err(|e| e.context(format!("error while reading the header of {}", lang).as_str()))?;
+    if format != Format::Pretty {
+        term.print(output::vocabulary(lang, &header, &voc, format)?)
+            .unwrap();
+        return Ok(());
+    }
+    if !header.is_empty() {
+        term.print(pretty_print::pretty_header(lang, &header))
+            .unwrap();
+    }
+    term.println(pretty_print::pretty_print(voc)).unwrap();
+    Ok(())
+}
@@ -0,0 +1,30 @@
+use crate::args::Params;
+use crate::cfg::Config;
+use crate::commands::open_storage;
+use crate::error::*;
+use crate::output::{self, Format};
+use crate::pretty_print;
+use crate::query::{self, LanguageHits, Query};
+use btui::Terminal;
+use std::io::{stdout, IsTerminal};
+
+/// search every language for the vocabulary matching the query of `-q`
+pub fn run(params: &Params, conf: &Config, term: &Terminal) -> Result<(), VctError> {
+    let format: Format = Format::from_name(params.format.as_deref())?;
+    let storage = open_storage(params, conf)?;
+    let (hits, q): (Vec<LanguageHits>, Query) =
+        Query::parse(params.query.as_deref().unwrap_or_default())
+            .and_then(|q| Ok((query::query(&q, &*storage)?, q)))
+            .map_err(|e| e.context("error querying files"))?;
+    if format == Format::Pretty {
+        term.print(pretty_print::pretty_results(
+            &hits,
+            &q,
+            stdout().is_terminal(),
+        ))
+        .unwrap();
+    } else {
+        term.print(output::query_results(&hits, format)?).unwrap();
+    }
+    Ok(())
+}
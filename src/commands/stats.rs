@@ -0,0 +1,16 @@
+use crate::args::Params;
+use crate::cfg::Config;
+use crate::error::*;
+use crate::history::History;
+use crate::stats;
+use btui::Terminal;
+
+/// show the accuracy of past sessions per language and week (or per week and vocab of a language)
+pub fn run(params: &Params, conf: &Config, term: &Terminal) -> Result<(), VctError> {
+    let history: History = History::open(conf.db_path(params.config_dir.as_str()).as_str())
+        .map_err(|e| e.context("error opening session history"))?;
+    let summary: String = stats::stats(&history, params.command_args.first().map(|x| x.as_str()))
+        .map_err(|e| e.context("error summarising history"))?;
+    term.println(summary).unwrap();
+    Ok(())
+}
@@ -0,0 +1,348 @@
+use crate::dict::{escape_list, Vocab};
+use crate::edit::split_list;
+use crate::error::*;
+use std::path::Path;
+
+/// enum representing what a column of a CSV/TSV file contains
+#[derive(Debug, Clone, PartialEq)]
+pub enum Column {
+    /// the vocab in the language to learn
+    Name,
+    /// the comma seperated meanings
+    Meanings,
+    /// the comma seperated tags
+    Tags,
+    /// the value of an additional with the key
+    Additional(String),
+    /// a column that is skipped
+    Ignore,
+}
+
+impl Column {
+    /// get the column from its name in a column list or header row
+    pub fn from_name(name: &str) -> Column {
+        match name.trim().to_lowercase().as_str() {
+            "name" => Column::Name,
+            "meanings" | "meaning" => Column::Meanings,
+            "tags" | "tag" => Column::Tags,
+            "-" | "" => Column::Ignore,
+            _ => Column::Additional(name.trim().to_string()),
+        }
+    }
+
+    /// get the name of the column as it is written to a header row
+    pub fn name(&self) -> String {
+        match self {
+            Column::Name => String::from("name"),
+            Column::Meanings => String::from("meanings"),
+            Column::Tags => String::from("tags"),
+            Column::Additional(n) => n.clone(),
+            Column::Ignore => String::from("-"),
+        }
+    }
+}
+
+/// a row of an imported file that was not imported
+#[derive(Debug, Clone)]
+pub struct Rejected {
+    /// the line the row starts on
+    pub line: u64,
+    /// why the row was rejected
+    pub reason: String,
+}
+
+/// parse a comma seperated column list (e.g. `name,meanings,gender,tags`)
+/// # Arguments
+/// * `columns`: the column list
+pub fn parse_columns(columns: &str) -> Result<Vec<Column>, VctError> {
+    let out: Vec<Column> = columns.split(',').map(Column::from_name).collect();
+    check_columns(&out)?;
+    Ok(out)
+}
+
+/// make sure the name and meanings of a vocab are mapped to exactly one column
+fn check_columns(columns: &[Column]) -> Result<(), VctError> {
+    for needed in [Column::Name, Column::Meanings] {
+        match columns.iter().filter(|x| **x == needed).count() {
+            0 => {
+                return Err(VctError::new(
+                    VctErrorKind::ParamError,
+                    format!("no '{}' column", needed.name()).as_str(),
+                ));
+            }
+            1 => (),
+            _ => {
+                return Err(VctError::new(
+                    VctErrorKind::ParamError,
+                    format!("more than one '{}' column", needed.name()).as_str(),
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// get the delimiter of a file from the format (`csv` or `tsv`) or the extension of the file
+/// # Arguments
+/// * `path`: the path to the file
+/// * `format`: the format given with `--delimiter`
+pub fn delimiter(path: &str, format: Option<&str>) -> Result<u8, VctError> {
+    let format: String = match format {
+        Some(n) => n.to_lowercase(),
+        None => Path::new(path)
+            .extension()
+            .map(|x| x.to_string_lossy().to_lowercase())
+            .unwrap_or_default(),
+    };
+    match format.as_str() {
+        "csv" => Ok(b','),
+        "tsv" | "tab" => Ok(b'\t'),
+        n => Err(VctError::new(
+            VctErrorKind::ParamError,
+            format!("unknown format '{}'. Valid are 'csv' and 'tsv'", n).as_str(),
+        )),
+    }
+}
+
+/// create a vocab from the cells of a row
+/// # Arguments
+/// * `record`: the cells of the row
+/// * `columns`: what the cells contain
+/// # Returns
+/// the vocab or why the row can't be imported
+pub fn vocab_from_record(record: &csv::StringRecord, columns: &[Column]) -> Result<Vocab, String> {
+    if record.len() > columns.len() {
+        return Err(format!(
+            "{} cells but only {} columns",
+            record.len(),
+            columns.len()
+        ));
+    }
+    let mut name: String = String::new();
+    let mut meanings: Vec<String> = Vec::new();
+    let mut additionals: Vec<String> = Vec::new();
+    let mut tags: Vec<String> = Vec::new();
+    for (cell, column) in record.iter().zip(columns) {
+        match column {
+            Column::Name => name = cell.trim().to_string(),
+            Column::Meanings => meanings = split_list(cell),
+            Column::Tags => tags = split_list(cell),
+            Column::Additional(key) => {
+                for value in split_list(cell) {
+                    additionals.push(format!("{}:{}", key, value));
+                }
+            }
+            _ => (),
+        }
+    }
+    let additionals: Option<Vec<String>> = if additionals.is_empty() {
+        None
+    } else {
+        Some(additionals)
+    };
+    let vocab: Vocab = Vocab::new(name, meanings, additionals, tags);
+    match vocab.validate() {
+        Ok(_) => Ok(vocab),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// read vocabulary from a CSV/TSV file
+/// # Arguments
+/// * `path`: the path to the file
+/// * `delimiter`: the delimiter of the cells
+/// * `columns`: what the columns contain (taken from the header row if `None`)
+/// * `header`: whether the first row is a header row
+/// # Returns
+/// the imported vocabulary and the rejected rows
+pub fn import(
+    path: &str,
+    delimiter: u8,
+    columns: Option<Vec<Column>>,
+    header: bool,
+) -> Result<(Vec<Vocab>, Vec<Rejected>), VctError> {
+    let mut reader = match csv::ReaderBuilder::new()
+        .delimiter(delimiter)
+        .has_headers(header)
+        .flexible(true)
+        .from_path(path)
+    {
+        Ok(n) => n,
+        Err(e) => {
+            return Err(VctError::new(
+                VctErrorKind::FileError,
+                format!("error opening '{}': {}", path, e).as_str(),
+            ));
+        }
+    };
+    let columns: Vec<Column> = match columns {
+        Some(n) => n,
+        None if header => match reader.headers() {
+            Ok(n) => {
+                let out: Vec<Column> = n.iter().map(Column::from_name).collect();
+                check_columns(&out)?;
+                out
+            }
+            Err(e) => {
+                return Err(VctError::new(
+                    VctErrorKind::ParsingError,
+                    format!("error reading header row: {}", e).as_str(),
+                ));
+            }
+        },
+        None => vec![Column::Name, Column::Meanings],
+    };
+    let mut vocab: Vec<Vocab> = Vec::new();
+    let mut rejected: Vec<Rejected> = Vec::new();
+    for record in reader.records() {
+        match record {
+            Ok(n) => {
+                let line: u64 = n.position().map(|x| x.line()).unwrap_or(0);
+                match vocab_from_record(&n, &columns) {
+                    Ok(v) => vocab.push(v),
+                    Err(reason) => rejected.push(Rejected { line, reason }),
+                }
+            }
+            Err(e) => {
+                let line: u64 = e.position().map(|x| x.line()).unwrap_or(0);
+                rejected.push(Rejected {
+                    line,
+                    reason: e.to_string(),
+                });
+            }
+        }
+    }
+    Ok((vocab, rejected))
+}
+
+/// get the default columns for exporting vocabulary: name, meanings, every additional key (in
+/// the order they first appear in) and tags
+/// # Arguments
+/// * `vocab`: the vocabulary to export
+pub fn default_columns(vocab: &[Vocab]) -> Vec<Column> {
+    let mut out: Vec<Column> = vec![Column::Name, Column::Meanings];
+    for voc in vocab {
+        for add in voc.get_additionals().unwrap_or_default() {
+            let key: Column = Column::Additional(add.split(':').next().unwrap_or("").to_string());
+            if !out.contains(&key) {
+                out.push(key);
+            }
+        }
+    }
+    out.push(Column::Tags);
+    out
+}
+
+/// get the cells of a vocab. Lists are joined with commas, which are escaped inside the items like
+/// in a dict file
+/// # Arguments
+/// * `voc`: the vocab
+/// * `columns`: what the cells contain
+pub fn record(voc: &Vocab, columns: &[Column]) -> Vec<String> {
+    let additionals: Vec<String> = voc.get_additionals().unwrap_or_default();
+    columns
+        .iter()
+        .map(|column| match column {
+            Column::Name => voc.get_name(),
+            Column::Meanings => escape_list(&voc.get_meanings()),
+            Column::Tags => escape_list(&voc.get_tags()),
+            Column::Additional(key) => escape_list(
+                &additionals
+                    .iter()
+                    .filter_map(|x| x.split_once(':'))
+                    .filter(|(k, _)| k == key)
+                    .map(|(_, v)| v.to_string())
+                    .collect::<Vec<String>>(),
+            ),
+            Column::Ignore => String::new(),
+        })
+        .collect()
+}
+
+/// write vocabulary to a CSV/TSV file with a header row
+/// # Arguments
+/// * `path`: the path to the file
+/// * `delimiter`: the delimiter of the cells
+/// * `columns`: what the columns contain
+/// * `vocab`: the vocabulary to export
+pub fn export(
+    path: &str,
+    delimiter: u8,
+    columns: &[Column],
+    vocab: &[Vocab],
+) -> Result<(), VctError> {
+    let file_error = |e: csv::Error| {
+        VctError::new(
+            VctErrorKind::FileError,
+            format!("error writing '{}': {}", path, e).as_str(),
+        )
+    };
+    let mut writer = csv::WriterBuilder::new()
+        .delimiter(delimiter)
+        .from_path(path)
+        .map_err(file_error)?;
+    writer
+        .write_record(columns.iter().map(|x| x.name()))
+        .map_err(file_error)?;
+    for voc in vocab {
+        writer
+            .write_record(record(voc, columns))
+            .map_err(file_error)?;
+    }
+    writer.flush().map_err(|e| {
+        VctError::new(
+            VctErrorKind::FileError,
+            format!("error writing '{}': {}", path, e).as_str(),
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn export_import_round_trip() {
+        let vocab: Vec<Vocab> = vec![
+            Vocab::new(
+                String::from("legen"),
+                vec![String::from("to put, to place"), String::from("to lay")],
+                Some(vec![
+                    String::from("example:ich lege, du legst"),
+                    String::from("example:er legt"),
+                    String::from("type:weak\\regular"),
+                ]),
+                vec![String::from("verb")],
+            ),
+            Vocab::new(
+                String::from("Hund"),
+                vec![String::from("dog")],
+                None,
+                Vec::new(),
+            ),
+        ];
+        for delimiter in [b',', b'\t'] {
+            let path = std::env::temp_dir().join(format!(
+                "vct-roundtrip-{}-{}.csv",
+                std::process::id(),
+                delimiter
+            ));
+            let path: &str = path.to_str().unwrap();
+            export(path, delimiter, &default_columns(&vocab), &vocab).unwrap();
+            let (imported, rejected) = import(path, delimiter, None, true).unwrap();
+            std::fs::remove_file(path).unwrap();
+            assert!(rejected.is_empty());
+            assert_eq!(imported, vocab);
+        }
+    }
+
+    #[test]
+    fn split_cells_on_unescaped_commas() {
+        let columns: Vec<Column> = vec![Column::Name, Column::Meanings, Column::Tags];
+        let row =
+            csv::StringRecord::from(vec!["mettre", "to put\\, to place, to set", "verb, a\\,b"]);
+        let voc: Vocab = vocab_from_record(&row, &columns).unwrap();
+        assert_eq!(voc.get_meanings(), vec!["to put, to place", "to set"]);
+        assert_eq!(voc.get_tags(), vec!["verb", "a,b"]);
+    }
+}
@@ -0,0 +1,527 @@
+use crate::dict::{vocab_from_row, Vocab};
+use crate::error::*;
+use rusqlite::{params, Connection, OptionalExtension, Transaction};
+
+/// a migration upgrading the database by one version
+type Migration = fn(&Transaction) -> Result<(), VctError>;
+
+/// all migrations in order. The `user_version` of a database is the amount of migrations that
+/// were applied to it
+const MIGRATIONS: [Migration; 6] = [
+    create_vocab,
+    add_tags,
+    normalize,
+    add_headers,
+    add_reviews,
+    add_history,
+];
+
+/// version 1: the single vocab table of older versions of vct
+fn create_vocab(tx: &Transaction) -> Result<(), VctError> {
+    tx.execute("CREATE TABLE IF NOT EXISTS vocab (lang VARCHAR(256) NOT NULL, name VARCHAR(256) NOT NULL, meanings VARCHAR(256) NOT NULL, additionals VARCHAR(256))", [])
+        .map_err(|e| db_error("error creating the vocab table", e))?;
+    Ok(())
+}
+
+/// version 2: tags of the vocab
+fn add_tags(tx: &Transaction) -> Result<(), VctError> {
+    if tx.prepare("SELECT tags FROM vocab LIMIT 0").is_err() {
+        tx.execute("ALTER TABLE vocab ADD COLUMN tags VARCHAR(256)", [])
+            .map_err(|e| db_error("error adding the tags column", e))?;
+    }
+    Ok(())
+}
+
+/// a row of the vocab table of version 2: rowid, language, name, meanings, additionals and tags
+type LegacyRow = (i64, String, String, String, Option<String>, Option<String>);
+
+/// version 3: split the vocab table into languages, entries, meanings, additionals and tags. A row
+/// that can't be converted without losing data stops the migration
+fn normalize(tx: &Transaction) -> Result<(), VctError> {
+    let failed = |e: rusqlite::Error| db_error("error splitting the vocab table", e);
+    tx.execute_batch(
+        "CREATE TABLE languages (id INTEGER PRIMARY KEY, name TEXT NOT NULL UNIQUE);
+        CREATE TABLE entries (id INTEGER PRIMARY KEY, language INTEGER NOT NULL REFERENCES languages(id) ON DELETE CASCADE, name TEXT NOT NULL);
+        CREATE TABLE meanings (entry INTEGER NOT NULL REFERENCES entries(id) ON DELETE CASCADE, position INTEGER NOT NULL, meaning TEXT NOT NULL, PRIMARY KEY (entry, position));
+        CREATE TABLE additionals (entry INTEGER NOT NULL REFERENCES entries(id) ON DELETE CASCADE, position INTEGER NOT NULL, key TEXT NOT NULL, value TEXT, PRIMARY KEY (entry, position));
+        CREATE TABLE tags (entry INTEGER NOT NULL REFERENCES entries(id) ON DELETE CASCADE, position INTEGER NOT NULL, tag TEXT NOT NULL, PRIMARY KEY (entry, position));
+        CREATE INDEX entries_language ON entries(language);",
+    )
+    .map_err(failed)?;
+    let rows: Vec<LegacyRow> = {
+        let mut sel = tx
+            .prepare(
+                "SELECT rowid, lang, name, meanings, additionals, tags FROM vocab ORDER BY rowid",
+            )
+            .map_err(failed)?;
+        let rows = sel
+            .query_map([], |row| {
+                Ok((
+                    row.get(0)?,
+                    row.get(1)?,
+                    row.get(2)?,
+                    row.get(3)?,
+                    row.get(4)?,
+                    row.get(5)?,
+                ))
+            })
+            .map_err(failed)?;
+        rows.collect::<rusqlite::Result<Vec<LegacyRow>>>()
+            .map_err(failed)?
+    };
+    for (rowid, lang, name, meanings, additionals, tags) in rows {
+        let vocab: Vocab = match vocab_from_row(name.clone(), meanings, additionals, tags) {
+            Ok(n) => n,
+            Err(e) => {
+                return Err(VctError::new(
+                    VctErrorKind::ParsingError,
+                    format!(
+                        "row {} of the vocab table ({} '{}') can't be converted ({}). Fix or delete it (e.g. `DELETE FROM vocab WHERE rowid = {}` in sqlite3) and run vct again",
+                        rowid, lang, name, e, rowid
+                    )
+                    .as_str(),
+                ));
+            }
+        };
+        insert(tx, lang.as_str(), &vocab).map_err(failed)?;
+    }
+    tx.execute("DROP TABLE vocab", []).map_err(failed)?;
+    Ok(())
+}
+
+/// version 4: metadata of the languages
+fn add_headers(tx: &Transaction) -> Result<(), VctError> {
+    tx.execute_batch(
+        "CREATE TABLE headers (language INTEGER NOT NULL REFERENCES languages(id) ON DELETE CASCADE, key TEXT NOT NULL, value TEXT NOT NULL, PRIMARY KEY (language, key));",
+    )
+    .map_err(|e| db_error("error creating the headers table", e))
+}
+
+/// version 5: the review state of the SM-2 and leitner schedulers. Older versions of vct created
+/// these tables outside of the migrations, so they may already exist
+fn add_reviews(tx: &Transaction) -> Result<(), VctError> {
+    tx.execute_batch(
+        "CREATE TABLE IF NOT EXISTS review (lang VARCHAR(256) NOT NULL, name VARCHAR(256) NOT NULL, ease REAL NOT NULL, interval INTEGER NOT NULL, repetitions INTEGER NOT NULL, due INTEGER NOT NULL, PRIMARY KEY (lang, name));
+        CREATE TABLE IF NOT EXISTS leitner (lang VARCHAR(256) NOT NULL, name VARCHAR(256) NOT NULL, box INTEGER NOT NULL, last_review INTEGER NOT NULL, PRIMARY KEY (lang, name));",
+    )
+    .map_err(|e| db_error("error creating the review tables", e))
+}
+
+/// version 6: the session history. Like the review tables these may already exist
+fn add_history(tx: &Transaction) -> Result<(), VctError> {
+    tx.execute_batch(
+        "CREATE TABLE IF NOT EXISTS sessions (id INTEGER PRIMARY KEY, lang VARCHAR(256) NOT NULL, started INTEGER NOT NULL, finished INTEGER NOT NULL);
+        CREATE TABLE IF NOT EXISTS answers (session INTEGER NOT NULL REFERENCES sessions(id), time INTEGER NOT NULL, lang VARCHAR(256) NOT NULL, vocab VARCHAR(256) NOT NULL, answer VARCHAR(256) NOT NULL, result VARCHAR(16) NOT NULL, kind VARCHAR(16) NOT NULL);",
+    )
+    .map_err(|e| db_error("error creating the history tables", e))
+}
+
+/// open the database and upgrade it to the newest version
+/// # Arguments
+/// * `path`: path to the database
+pub fn open(path: &str) -> Result<Connection, VctError> {
+    let mut conn = match Connection::open(path) {
+        Ok(n) => n,
+        Err(_) => {
+            return Err(VctError::new(
+                VctErrorKind::DatabaseError,
+                "error connecting to database",
+            ));
+        }
+    };
+    if let Err(e) = conn.execute_batch("PRAGMA foreign_keys = ON") {
+        return Err(VctError::new(
+            VctErrorKind::DatabaseError,
+            format!("error enabling foreign keys: {}", e).as_str(),
+        ));
+    }
+    migrate(&mut conn)?;
+    Ok(conn)
+}
+
+/// apply all migrations the database is missing, each in its own transaction
+/// # Arguments
+/// * `conn`: the connection to the database
+fn migrate(conn: &mut Connection) -> Result<(), VctError> {
+    let version: usize = match conn.query_row("PRAGMA user_version", [], |row| row.get::<_, i64>(0))
+    {
+        Ok(n) => n as usize,
+        Err(e) => {
+            return Err(VctError::new(
+                VctErrorKind::DatabaseError,
+                format!("error reading database version: {}", e).as_str(),
+            ));
+        }
+    };
+    if version > MIGRATIONS.len() {
+        return Err(VctError::new(
+            VctErrorKind::DatabaseError,
+            format!(
+                "database version {} is newer than this version of vct supports ({})",
+                version,
+                MIGRATIONS.len()
+            )
+            .as_str(),
+        ));
+    }
+    for (idx, migration) in MIGRATIONS.iter().enumerate().skip(version) {
+        let upgrade_error = |e: &dyn std::fmt::Display| {
+            VctError::new(
+                VctErrorKind::DatabaseError,
+                format!("error upgrading database to version {}: {}", idx + 1, e).as_str(),
+            )
+        };
+        // the transaction is rolled back if it is dropped without being committed
+        let tx: Transaction = conn.transaction().map_err(|e| upgrade_error(&e))?;
+        migration(&tx).map_err(|e| upgrade_error(&e))?;
+        tx.pragma_update(None, "user_version", (idx + 1) as i64)
+            .and_then(|_| tx.commit())
+            .map_err(|e| upgrade_error(&e))?;
+    }
+    Ok(())
+}
+
+/// convert a rusqlite error to a `VctError`
+pub fn db_error(msg: &str, e: rusqlite::Error) -> VctError {
+    VctError::new(
+        VctErrorKind::DatabaseError,
+        format!("{}: {}", msg, e).as_str(),
+    )
+}
+
+/// get the id of a language and create it if it doesn't exist
+fn language_id(conn: &Connection, lang: &str) -> rusqlite::Result<i64> {
+    conn.execute(
+        "INSERT OR IGNORE INTO languages (name) VALUES (?)",
+        params![lang],
+    )?;
+    conn.query_row(
+        "SELECT id FROM languages WHERE name = ?",
+        params![lang],
+        |row| row.get(0),
+    )
+}
+
+/// insert a vocab into a language
+/// # Arguments
+/// * `conn`: the connection to the database
+/// * `lang`: the language
+/// * `vocab`: the vocab to insert
+/// # Returns
+/// the id of the new entry
+pub fn insert(conn: &Connection, lang: &str, vocab: &Vocab) -> rusqlite::Result<i64> {
+    let language: i64 = language_id(conn, lang)?;
+    conn.execute(
+        "INSERT INTO entries (language, name) VALUES (?, ?)",
+        params![language, vocab.get_name()],
+    )?;
+    let entry: i64 = conn.last_insert_rowid();
+    insert_children(conn, entry, vocab)?;
+    Ok(entry)
+}
+
+/// insert the meanings, additionals and tags of a vocab
+fn insert_children(conn: &Connection, entry: i64, vocab: &Vocab) -> rusqlite::Result<()> {
+    for (idx, meaning) in vocab.get_meanings().iter().enumerate() {
+        conn.execute(
+            "INSERT INTO meanings (entry, position, meaning) VALUES (?, ?, ?)",
+            params![entry, idx as i64, meaning],
+        )?;
+    }
+    for (idx, add) in vocab
+        .get_additionals()
+        .unwrap_or_default()
+        .iter()
+        .enumerate()
+    {
+        let (key, value): (&str, Option<&str>) = match add.split_once(':') {
+            Some((k, v)) => (k, Some(v)),
+            None => (add.as_str(), None),
+        };
+        conn.execute(
+            "INSERT INTO additionals (entry, position, key, value) VALUES (?, ?, ?, ?)",
+            params![entry, idx as i64, key, value],
+        )?;
+    }
+    for (idx, tag) in vocab.get_tags().iter().enumerate() {
+        conn.execute(
+            "INSERT INTO tags (entry, position, tag) VALUES (?, ?, ?)",
+            params![entry, idx as i64, tag],
+        )?;
+    }
+    Ok(())
+}
+
+/// check whether an entry belongs to a language
+fn in_language(conn: &Connection, lang: &str, entry: i64) -> rusqlite::Result<bool> {
+    conn.query_row(
+        "SELECT COUNT(*) FROM entries JOIN languages ON entries.language = languages.id WHERE entries.id = ? AND languages.name = ?",
+        params![entry, lang],
+        |row| row.get::<_, i64>(0),
+    )
+    .map(|x| x > 0)
+}
+
+/// replace the vocab of an entry
+/// # Arguments
+/// * `conn`: the connection to the database
+/// * `lang`: the language of the entry
+/// * `entry`: the id of the entry
+/// * `vocab`: the new vocab
+/// # Returns
+/// whether the entry exists in the language
+pub fn update(conn: &Connection, lang: &str, entry: i64, vocab: &Vocab) -> rusqlite::Result<bool> {
+    if !in_language(conn, lang, entry)? {
+        return Ok(false);
+    }
+    conn.execute(
+        "UPDATE entries SET name = ? WHERE id = ?",
+        params![vocab.get_name(), entry],
+    )?;
+    for table in ["meanings", "additionals", "tags"] {
+        conn.execute(
+            format!("DELETE FROM {} WHERE entry = ?", table).as_str(),
+            params![entry],
+        )?;
+    }
+    insert_children(conn, entry, vocab)?;
+    Ok(true)
+}
+
+/// delete an entry
+/// # Arguments
+/// * `conn`: the connection to the database
+/// * `lang`: the language of the entry
+/// * `entry`: the id of the entry
+/// # Returns
+/// whether the entry existed in the language
+pub fn delete(conn: &Connection, lang: &str, entry: i64) -> rusqlite::Result<bool> {
+    if !in_language(conn, lang, entry)? {
+        return Ok(false);
+    }
+    conn.execute("DELETE FROM entries WHERE id = ?", params![entry])?;
+    Ok(true)
+}
+
+/// read a single column of an entry's child table in order
+fn children(conn: &Connection, sql: &str, entry: i64) -> rusqlite::Result<Vec<String>> {
+    let mut sel = conn.prepare_cached(sql)?;
+    let rows = sel.query_map(params![entry], |row| row.get(0))?;
+    rows.collect()
+}
+
+/// build the vocab of an entry
+fn entry_vocab(conn: &Connection, entry: i64, name: String) -> rusqlite::Result<Vocab> {
+    let meanings: Vec<String> = children(
+        conn,
+        "SELECT meaning FROM meanings WHERE entry = ? ORDER BY position",
+        entry,
+    )?;
+    let additionals: Vec<String> = children(
+        conn,
+        "SELECT CASE WHEN value IS NULL THEN key ELSE key || ':' || value END FROM additionals WHERE entry = ? ORDER BY position",
+        entry,
+    )?;
+    let tags: Vec<String> = children(
+        conn,
+        "SELECT tag FROM tags WHERE entry = ? ORDER BY position",
+        entry,
+    )?;
+    let additionals: Option<Vec<String>> = if additionals.is_empty() {
+        None
+    } else {
+        Some(additionals)
+    };
+    Ok(Vocab::new(name, meanings, additionals, tags))
+}
+
+/// load the vocabulary of a language (or of all languages) in the order it was added
+/// # Arguments
+/// * `conn`: the connection to the database
+/// * `lang`: the language (all languages if `None`)
+/// # Returns
+/// the language, id and vocab of every entry
+pub fn load(conn: &Connection, lang: Option<&str>) -> Result<Vec<(String, i64, Vocab)>, VctError> {
+    let entries = conn
+        .prepare("SELECT entries.id, entries.name, languages.name FROM entries JOIN languages ON entries.language = languages.id WHERE (?1 IS NULL OR languages.name = ?1) ORDER BY entries.id")
+        .and_then(|mut sel| {
+            sel.query_map(params![lang], |row| {
+                Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?, row.get::<_, String>(2)?))
+            })?
+            .collect::<rusqlite::Result<Vec<(i64, String, String)>>>()
+        })
+        .map_err(|e| db_error("error reading vocabulary", e))?;
+    let mut out: Vec<(String, i64, Vocab)> = Vec::new();
+    for (id, name, language) in entries {
+        let vocab: Vocab =
+            entry_vocab(conn, id, name).map_err(|e| db_error("error reading vocabulary", e))?;
+        out.push((language, id, vocab));
+    }
+    Ok(out)
+}
+
+/// list all languages with vocabulary
+/// # Arguments
+/// * `conn`: the connection to the database
+pub fn languages(conn: &Connection) -> Result<Vec<String>, VctError> {
+    conn.prepare("SELECT name FROM languages WHERE EXISTS (SELECT 1 FROM entries WHERE entries.language = languages.id) ORDER BY name")
+        .and_then(|mut sel| {
+            sel.query_map([], |row| row.get(0))?
+                .collect::<rusqlite::Result<Vec<String>>>()
+        })
+        .map_err(|e| db_error("error listing languages", e))
+}
+
+/// remove a language with all of its vocabulary
+/// # Arguments
+/// * `conn`: the connection to the database
+/// * `lang`: the language to remove
+pub fn remove_language(conn: &Connection, lang: &str) -> Result<(), VctError> {
+    let id: Option<i64> = conn
+        .query_row(
+            "SELECT id FROM languages WHERE name = ?",
+            params![lang],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| db_error("error removing language", e))?;
+    if let Some(n) = id {
+        conn.execute("DELETE FROM languages WHERE id = ?", params![n])
+            .map_err(|e| db_error("error removing language", e))?;
+    }
+    Ok(())
+}
+
+/// get the metadata of a language
+/// # Arguments
+/// * `conn`: the connection to the database
+/// * `lang`: the language
+/// # Returns
+/// pairs of a header key and its value
+pub fn header(conn: &Connection, lang: &str) -> Result<Vec<(String, String)>, VctError> {
+    conn.prepare("SELECT headers.key, headers.value FROM headers JOIN languages ON headers.language = languages.id WHERE languages.name = ?")
+        .and_then(|mut sel| {
+            sel.query_map(params![lang], |row| Ok((row.get(0)?, row.get(1)?)))?
+                .collect::<rusqlite::Result<Vec<(String, String)>>>()
+        })
+        .map_err(|e| db_error("error reading header", e))
+}
+
+/// replace the metadata of a language
+/// # Arguments
+/// * `conn`: the connection to the database
+/// * `lang`: the language (created if it doesn't exist)
+/// * `pairs`: pairs of a header key and its value
+pub fn set_header(conn: &Connection, lang: &str, pairs: &[(&str, String)]) -> rusqlite::Result<()> {
+    let language: i64 = language_id(conn, lang)?;
+    conn.execute("DELETE FROM headers WHERE language = ?", params![language])?;
+    for (key, value) in pairs {
+        conn.execute(
+            "INSERT INTO headers (language, key, value) VALUES (?, ?, ?)",
+            params![language, key, value],
+        )?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// language, name, meanings, additionals and tags of a row of the old vocab table
+    type Row<'a> = (&'a str, &'a str, &'a str, Option<&'a str>, Option<&'a str>);
+
+    /// a database as older versions of vct left it: one vocab table with tags and no version
+    fn legacy(rows: &[Row]) -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch("CREATE TABLE vocab (lang VARCHAR(256) NOT NULL, name VARCHAR(256) NOT NULL, meanings VARCHAR(256) NOT NULL, additionals VARCHAR(256), tags VARCHAR(256))")
+            .unwrap();
+        for row in rows {
+            conn.execute(
+                "INSERT INTO vocab (lang, name, meanings, additionals, tags) VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![row.0, row.1, row.2, row.3, row.4],
+            )
+            .unwrap();
+        }
+        conn
+    }
+
+    fn version(conn: &Connection) -> i64 {
+        conn.query_row("PRAGMA user_version", [], |row| row.get(0))
+            .unwrap()
+    }
+
+    #[test]
+    fn migrate_legacy_table() {
+        let mut conn = legacy(&[
+            ("de", "legen", "lay,put", Some("past:legte"), Some("verb")),
+            ("de", "das Haus", "house", None, None),
+            ("fr", "la maison", "house", None, Some("noun")),
+        ]);
+        migrate(&mut conn).unwrap();
+        assert_eq!(version(&conn), MIGRATIONS.len() as i64);
+        assert_eq!(languages(&conn).unwrap(), vec!["de", "fr"]);
+        let vocab: Vec<Vocab> = load(&conn, Some("de"))
+            .unwrap()
+            .into_iter()
+            .map(|(_, _, x)| x)
+            .collect();
+        assert_eq!(vocab.len(), 2);
+        assert_eq!(vocab[0].get_name(), "legen");
+        assert_eq!(vocab[0].get_meanings(), vec!["lay", "put"]);
+        assert_eq!(
+            vocab[0].get_additionals(),
+            Some(vec![String::from("past:legte")])
+        );
+        assert_eq!(vocab[0].get_tags(), vec!["verb"]);
+        assert!(conn.prepare("SELECT * FROM vocab").is_err());
+
+        // a current database is left as it is
+        migrate(&mut conn).unwrap();
+        assert_eq!(version(&conn), MIGRATIONS.len() as i64);
+    }
+
+    #[test]
+    fn migrate_keeps_review_and_history() {
+        // older versions of vct created these tables without raising the version
+        let mut conn = legacy(&[("de", "legen", "lay", None, None)]);
+        conn.execute_batch(
+            "CREATE TABLE review (lang VARCHAR(256) NOT NULL, name VARCHAR(256) NOT NULL, ease REAL NOT NULL, interval INTEGER NOT NULL, repetitions INTEGER NOT NULL, due INTEGER NOT NULL, PRIMARY KEY (lang, name));
+            INSERT INTO review VALUES ('de', 'legen', 2.5, 1, 1, 100);
+            CREATE TABLE sessions (id INTEGER PRIMARY KEY, lang VARCHAR(256) NOT NULL, started INTEGER NOT NULL, finished INTEGER NOT NULL);
+            INSERT INTO sessions VALUES (1, 'de', 10, 20);",
+        )
+        .unwrap();
+        migrate(&mut conn).unwrap();
+        assert_eq!(version(&conn), MIGRATIONS.len() as i64);
+        let count = |table: &str| -> i64 {
+            conn.query_row(
+                format!("SELECT COUNT(*) FROM {}", table).as_str(),
+                [],
+                |row| row.get(0),
+            )
+            .unwrap()
+        };
+        assert_eq!(count("review"), 1);
+        assert_eq!(count("sessions"), 1);
+        assert_eq!(count("leitner"), 0);
+        assert_eq!(count("answers"), 0);
+    }
+
+    #[test]
+    fn migrate_stops_at_broken_row() {
+        let mut conn = legacy(&[
+            ("de", "legen", "lay", None, None),
+            ("de", "", "house", None, None),
+        ]);
+        let err = migrate(&mut conn).unwrap_err().to_string();
+        assert!(err.contains("row 2"), "{}", err);
+        // the tables of the previous versions are kept for the user to fix the row
+        assert_eq!(version(&conn), 2);
+        let rows: i64 = conn
+            .query_row("SELECT COUNT(*) FROM vocab", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(rows, 2);
+        assert!(conn.prepare("SELECT * FROM entries").is_err());
+    }
+}
@@ -0,0 +1,365 @@
+use crate::dict::Vocab;
+use crate::error::*;
+use crate::normalize::Normalizer;
+use crate::storage::{Entry, Storage};
+use btui::effects::{Color, Special};
+use btui::print::{fg, sp};
+use btui::Terminal;
+use std::io::{stdout, Write};
+
+/// the values an additional key has in a group of duplicates
+#[derive(Debug, Clone, PartialEq)]
+pub struct MergedAdditional {
+    pub key: String,
+    /// the distinct values of the key in the order they appear
+    pub values: Vec<String>,
+    /// whether the duplicates disagree about the value (and only one of them should be kept)
+    pub conflict: bool,
+}
+
+/// vocabulary of a language with the same (normalized) name and the merge proposed for it
+#[derive(Debug, Clone)]
+pub struct Group {
+    /// the duplicates in the order they were added. The merge is stored in place of the first
+    pub entries: Vec<Entry>,
+    pub additionals: Vec<MergedAdditional>,
+}
+
+/// add the values to a list unless a normalized equal value already is in it
+fn union(out: &mut Vec<String>, values: &[String], normalizer: &Normalizer) {
+    for value in values {
+        let normalized: String = normalizer.normalize(value);
+        if !normalized.is_empty() && !out.iter().any(|x| normalizer.normalize(x) == normalized) {
+            out.push(value.trim().to_string());
+        }
+    }
+}
+
+/// get the values of an additional key in a vocab
+fn values_of(vocab: &Vocab, key: &str) -> Vec<String> {
+    vocab
+        .get_additionals()
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|x| x.split_once(':'))
+        .filter(|(k, _)| *k == key)
+        .map(|(_, v)| v.to_string())
+        .collect()
+}
+
+/// join the trimmed values of a list for a cell of the table
+fn list(values: &[String]) -> String {
+    values
+        .iter()
+        .map(|x| x.trim())
+        .collect::<Vec<&str>>()
+        .join(", ")
+}
+
+/// find the vocabulary of a language sharing a name
+/// # Arguments
+/// * `entries`: the vocabulary of the language
+/// * `normalizer`: how names and values are compared
+/// # Returns
+/// every group of duplicates in the order of their first entry
+pub fn find(entries: &[Entry], normalizer: &Normalizer) -> Vec<Group> {
+    let mut groups: Vec<(String, Vec<Entry>)> = Vec::new();
+    for entry in entries {
+        let name: String = normalizer.normalize(entry.vocab.get_name().as_str());
+        match groups.iter_mut().find(|(n, _)| n == &name) {
+            Some((_, n)) => n.push(entry.clone()),
+            None => groups.push((name, vec![entry.clone()])),
+        }
+    }
+    groups
+        .into_iter()
+        .filter(|(_, n)| n.len() > 1)
+        .map(|(_, entries)| {
+            let mut keys: Vec<String> = Vec::new();
+            for entry in &entries {
+                for add in entry.vocab.get_additionals().unwrap_or_default() {
+                    if let Some((key, _)) = add.split_once(':') {
+                        if !keys.iter().any(|x| x == key) {
+                            keys.push(key.to_string());
+                        }
+                    }
+                }
+            }
+            let additionals: Vec<MergedAdditional> = keys
+                .into_iter()
+                .map(|key| {
+                    let mut values: Vec<String> = Vec::new();
+                    let mut multiple: bool = false;
+                    for entry in &entries {
+                        let own: Vec<String> = values_of(&entry.vocab, key.as_str());
+                        multiple |= own.len() > 1;
+                        union(&mut values, &own, normalizer);
+                    }
+                    // a key with several values in one vocab (e.g. examples) is a list and
+                    // merged like the meanings
+                    let conflict: bool = values.len() > 1 && !multiple;
+                    MergedAdditional {
+                        key,
+                        values,
+                        conflict,
+                    }
+                })
+                .collect();
+            Group {
+                entries,
+                additionals,
+            }
+        })
+        .collect()
+}
+
+impl Group {
+    /// resolve the conflict of an additional by keeping only one value
+    /// # Arguments
+    /// * `key`: the key of the additional
+    /// * `value`: the index of the value to keep
+    pub fn resolve(&mut self, key: &str, value: usize) {
+        if let Some(n) = self.additionals.iter_mut().find(|x| x.key == key) {
+            if value < n.values.len() {
+                n.values = vec![n.values.remove(value)];
+                n.conflict = false;
+            }
+        }
+    }
+
+    /// get the merged vocab: the name of the first entry with the meanings, additionals and tags of
+    /// all of them. Additionals with an unresolved conflict keep every value
+    pub fn merged(&self, normalizer: &Normalizer) -> Vocab {
+        let mut meanings: Vec<String> = Vec::new();
+        let mut tags: Vec<String> = Vec::new();
+        for entry in &self.entries {
+            union(&mut meanings, &entry.vocab.get_meanings(), normalizer);
+            union(&mut tags, &entry.vocab.get_tags(), normalizer);
+        }
+        let additionals: Vec<String> = self
+            .additionals
+            .iter()
+            .flat_map(|x| x.values.iter().map(|v| format!("{}:{}", x.key, v)))
+            .collect();
+        Vocab::new(
+            self.entries[0].vocab.get_name(),
+            meanings,
+            if additionals.is_empty() {
+                None
+            } else {
+                Some(additionals)
+            },
+            tags,
+        )
+    }
+
+    /// show the duplicates and their merge side by side. Rows of conflicting additionals are marked
+    /// with `!`
+    pub fn table(&self, normalizer: &Normalizer) -> String {
+        let merged: Vocab = self.merged(normalizer);
+        let vocab: Vec<&Vocab> = self
+            .entries
+            .iter()
+            .map(|x| &x.vocab)
+            .chain(std::iter::once(&merged))
+            .collect();
+        let mut rows: Vec<(bool, String, Vec<String>)> = vec![
+            (
+                false,
+                String::new(),
+                self.entries
+                    .iter()
+                    .map(|x| format!("@{}", x.id))
+                    .chain(std::iter::once(String::from("merged")))
+                    .collect(),
+            ),
+            (
+                false,
+                String::from("name"),
+                vocab.iter().map(|x| x.get_name()).collect(),
+            ),
+            (
+                false,
+                String::from("meanings"),
+                vocab.iter().map(|x| list(&x.get_meanings())).collect(),
+            ),
+        ];
+        for add in &self.additionals {
+            let mut cells: Vec<String> = vocab
+                .iter()
+                .map(|x| match values_of(x, add.key.as_str()) {
+                    n if n.is_empty() => String::from("-"),
+                    n => list(&n),
+                })
+                .collect();
+            if add.conflict {
+                *cells.last_mut().unwrap() = String::from("?");
+            }
+            rows.push((add.conflict, add.key.clone(), cells));
+        }
+        rows.push((
+            false,
+            String::from("tags"),
+            vocab.iter().map(|x| list(&x.get_tags())).collect(),
+        ));
+
+        let width = |column: Option<usize>| -> usize {
+            rows.iter()
+                .map(|(_, label, cells)| match column {
+                    Some(n) => cells[n].chars().count(),
+                    None => label.chars().count(),
+                })
+                .max()
+                .unwrap_or(0)
+        };
+        let widths: Vec<usize> = (0..vocab.len()).map(|x| width(Some(x))).collect();
+        let label_width: usize = width(None);
+        let mut out: String = String::new();
+        for (idx, (conflict, label, cells)) in rows.iter().enumerate() {
+            let color: String = match (idx, conflict) {
+                (0, _) => fg(Color::White),
+                (_, true) => fg(Color::Red),
+                _ => String::new(),
+            };
+            let cells: Vec<String> = cells
+                .iter()
+                .zip(&widths)
+                .map(|(cell, w)| format!("{:<w$}", cell, w = w))
+                .collect();
+            out.push_str(
+                format!(
+                    "{}{} {:<w$} | {}{}\n",
+                    color,
+                    if *conflict { "!" } else { " " },
+                    label,
+                    cells.join(" | ").trim_end(),
+                    sp(Special::Reset),
+                    w = label_width
+                )
+                .as_str(),
+            );
+        }
+        out
+    }
+}
+
+/// what the user decided to do with a group of duplicates
+#[derive(Debug, Clone, PartialEq)]
+pub enum Decision {
+    Merge,
+    Skip,
+    /// skip this and every following group
+    Quit,
+}
+
+/// print a prompt and read the answer
+fn prompt(term: &Terminal, text: &str) -> Result<String, VctError> {
+    term.print(format!(
+        "{}{} > {}",
+        fg(Color::White),
+        text,
+        sp(Special::Reset)
+    ))
+    .unwrap();
+    let read = stdout()
+        .flush()
+        .and_then(|_| term.read_line_trimmed())
+        .map_err(|e| {
+            VctError::new(
+                VctErrorKind::ParamError,
+                format!("error reading input: {}", e).as_str(),
+            )
+        })?;
+    Ok(read.trim().to_lowercase())
+}
+
+/// ask the user how to resolve the conflicts of a group and whether to merge it
+/// # Arguments
+/// * `term`: the terminal
+/// * `group`: the group (its conflicts are resolved)
+/// # Returns
+/// the decision. Without an answer conflicting values are all kept and the group is skipped
+pub fn ask(term: &Terminal, group: &mut Group) -> Result<Decision, VctError> {
+    let conflicts: Vec<MergedAdditional> = group
+        .additionals
+        .iter()
+        .filter(|x| x.conflict)
+        .cloned()
+        .collect();
+    for add in conflicts {
+        let choices: Vec<String> = add
+            .values
+            .iter()
+            .enumerate()
+            .map(|(idx, x)| format!("{}) {}", idx + 1, x))
+            .collect();
+        loop {
+            let input: String = prompt(
+                term,
+                format!("{}: {}  a) keep all", add.key, choices.join("  ")).as_str(),
+            )?;
+            match input.as_str() {
+                "" | "a" => break,
+                n => match n.parse::<usize>() {
+                    Ok(i) if i >= 1 && i <= add.values.len() => {
+                        group.resolve(add.key.as_str(), i - 1);
+                        break;
+                    }
+                    _ => term
+                        .println(format!(
+                            "{}'{}' is not a valid choice{}",
+                            fg(Color::Red),
+                            n,
+                            sp(Special::Reset)
+                        ))
+                        .unwrap(),
+                },
+            }
+        }
+    }
+    loop {
+        let input: String = prompt(
+            term,
+            format!("merge into @{}? (y)es, (n)o, (q)uit", group.entries[0].id).as_str(),
+        )?;
+        match input.as_str() {
+            "y" | "yes" => return Ok(Decision::Merge),
+            "" | "n" | "no" => return Ok(Decision::Skip),
+            "q" | "quit" => return Ok(Decision::Quit),
+            n => term
+                .println(format!(
+                    "{}'{}' is not a valid choice{}",
+                    fg(Color::Red),
+                    n,
+                    sp(Special::Reset)
+                ))
+                .unwrap(),
+        }
+    }
+}
+
+/// store merged groups: the first entry of every group is replaced by the merge and the others
+/// are removed
+/// # Arguments
+/// * `storage`: the storage containing the language
+/// * `lang`: the language
+/// * `merges`: the groups and their merged vocab
+pub fn apply(
+    storage: &mut dyn Storage,
+    lang: &str,
+    merges: &[(Group, Vocab)],
+) -> Result<(), VctError> {
+    for (group, merged) in merges {
+        storage.update(lang, group.entries[0].id, merged)?;
+    }
+    // removing a line of a dict file changes the ids of the following ones
+    let mut removed: Vec<i64> = merges
+        .iter()
+        .flat_map(|(group, _)| group.entries[1..].iter().map(|x| x.id))
+        .collect();
+    removed.sort_unstable_by(|a, b| b.cmp(a));
+    for id in removed {
+        storage.delete(lang, id)?;
+    }
+    Ok(())
+}
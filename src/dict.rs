@@ -1,17 +1,15 @@
-use crate::cfg::*;
 use crate::error::*;
-use crate::info;
-use rusqlite::{params, Connection, Result};
-use std::fs::{create_dir_all, read_to_string, OpenOptions};
-use std::io::Write;
-use std::path::Path;
-use std::process::exit;
+use serde::ser::{SerializeStruct, Serializer};
+use serde::Serialize;
+use serde_derive::Serialize;
+use std::fmt;
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct Vocab {
     name: String,
     meanings: Vec<String>,
     additionals: Option<Vec<String>>,
+    tags: Vec<String>,
 }
 
 impl Vocab {
@@ -19,59 +17,131 @@ impl Vocab {
     /// # Arguments
     /// *`name`: the vocab in the language to learn
     /// *`meanings`: all meanings to learn
+    /// *`additionals`: additional information as `key:value` pairs
+    /// *`tags`: the categories the vocab belongs to
     /// # Returns
     /// a new abstracted vocabulary
-    pub fn new(name: String, meanings: Vec<String>, additionals: Option<Vec<String>>) -> Vocab {
+    pub fn new(
+        name: String,
+        meanings: Vec<String>,
+        additionals: Option<Vec<String>>,
+        tags: Vec<String>,
+    ) -> Vocab {
         Vocab {
             name,
             meanings,
             additionals,
+            tags,
         }
     }
 
-    /// parse a String to a vocab
+    /// parse a String to a vocab. A backslash in front of `;`, `,`, `:`, `#` or another backslash
+    /// makes it part of the text instead of a seperator
     /// # Arguments
     /// `string`: the string to parse
     /// # Returns
     /// a new vocabulary wrapped in a `Result`
     // TODO: use custom vct error
     pub fn from_string(string: String) -> Result<Vocab, VctError> {
-        let parts: Vec<&str> = string.as_str().split(';').collect();
+        Vocab::parse(string.as_str())
+            .map_err(|(_, msg)| VctError::new(VctErrorKind::ParsingError, msg))
+    }
+
+    /// parse a line of a dict file to a vocab
+    /// # Returns
+    /// the vocab or the column the line is invalid at together with the reason
+    fn parse(string: &str) -> Result<Vocab, (usize, &'static str)> {
+        let parts: Vec<String> = split_unescaped(string, ';');
         if parts.len() < 2 {
-            return Err(VctError::new(
-                VctErrorKind::ParsingError,
+            return Err((
+                string.chars().count() + 1,
                 "omitting necessary parts of vocabulary",
             ));
         }
-        let name: String = parts[0].to_string();
+        let name: String = unescape(parts[0].as_str());
         if name.is_empty() {
-            return Err(VctError::new(VctErrorKind::ParsingError, "empty name"));
+            return Err((1, "empty name"));
         }
-        let meanings_str: Vec<&str> = parts[1].split(',').collect();
-        let meanings: Vec<String> = meanings_str
+        let meanings: Vec<String> = split_unescaped(parts[1].as_str(), ',')
             .iter()
-            .map(|meaning| meaning.to_lowercase())
+            .map(|x| unescape(x))
             .collect();
         if meanings.is_empty() {
-            return Err(VctError::new(VctErrorKind::ParsingError, "empty meanings"));
+            return Err((parts[0].chars().count() + 2, "empty meanings"));
         }
-        if parts.len() > 2 {
-            let additionals_str: Vec<&str> = parts[2].split(',').collect();
-            let additionals: Vec<String> = additionals_str
+        let tags: Vec<String> = match parts.get(3) {
+            Some(n) => split_unescaped(n, ',')
                 .iter()
-                .map(|add| {
-                    let add_parts: Vec<&str> = add.split(':').collect();
-                    if (add_parts.len() % 2) != 0 {
-                        return String::new();
-                    }
-                    let key = add_parts[0];
-                    let val = add_parts[1];
-                    format!("{}:{}", key, val)
+                .map(|x| unescape(x).trim().to_string())
+                .filter(|x| !x.is_empty())
+                .collect(),
+            None => Vec::new(),
+        };
+        if parts.len() > 2 && !parts[2].is_empty() {
+            let additionals: Vec<String> = split_unescaped(parts[2].as_str(), ',')
+                .iter()
+                .map(|add| match split_once_unescaped(add, ':') {
+                    // everything after the first seperator is the value
+                    Some((key, val)) => format!("{}:{}", unescape(key), unescape(val)),
+                    None => String::new(),
                 })
                 .collect();
-            return Ok(Vocab::new(name, meanings, Some(additionals)));
+            return Ok(Vocab::new(name, meanings, Some(additionals), tags));
+        }
+        Ok(Vocab::new(name, meanings, None, tags))
+    }
+
+    /// convert the vocab to a line of a dict file (the format `from_string` parses). Seperators
+    /// inside the fields are escaped
+    /// # Returns
+    /// the line as a `String` (without a trailing newline)
+    pub fn to_line(&self) -> String {
+        let mut name: String = escape(self.name.as_str(), &[';']);
+        // a name starting with `#` would make the line a comment
+        if let Some(n) = name.find(|x: char| !x.is_whitespace()) {
+            if name[n..].starts_with('#') {
+                name.insert(n, '\\');
+            }
+        }
+        let mut out: String = format!("{};{}", name, escape_list(&self.meanings));
+        if let Some(n) = &self.additionals {
+            let adds: Vec<String> = n
+                .iter()
+                .map(|x| match x.split_once(':') {
+                    Some((key, value)) => format!(
+                        "{}:{}",
+                        escape(key, &[';', ',', ':']),
+                        escape(value, &[';', ','])
+                    ),
+                    None => escape(x, &[';', ',', ':']),
+                })
+                .collect();
+            out.push_str(format!(";{}", adds.join(",")).as_str());
+        }
+        if !self.tags.is_empty() {
+            if self.additionals.is_none() {
+                out.push(';');
+            }
+            out.push_str(format!(";{}", escape_list(&self.tags)).as_str());
+        }
+        out
+    }
+
+    /// make sure the vocab can be written to a dict file and read back unchanged
+    pub fn validate(&self) -> Result<(), VctError> {
+        if self.name.trim().is_empty() {
+            return Err(VctError::new(VctErrorKind::ParsingError, "empty name"));
+        }
+        if self.meanings.iter().all(|x| x.trim().is_empty()) {
+            return Err(VctError::new(VctErrorKind::ParsingError, "empty meanings"));
+        }
+        match Vocab::from_string(self.to_line()) {
+            Ok(n) if &n == self => Ok(()),
+            _ => Err(VctError::new(
+                VctErrorKind::ParsingError,
+                "can't be stored in a dict unchanged (e.g. an additional that isn't a key:value pair)",
+            )),
         }
-        Ok(Vocab::new(name, meanings, None))
     }
 
     /// get the meanings of a vocabulary
@@ -94,254 +164,290 @@ impl Vocab {
     pub fn get_additionals(&self) -> Option<Vec<String>> {
         self.additionals.clone()
     }
+
+    /// get the tags of the vocab
+    /// # Returns
+    /// the tags as a `Vec<String>`
+    pub fn get_tags(&self) -> Vec<String> {
+        self.tags.clone()
+    }
 }
 
-// TODO: use VctError
-pub fn load_vocab(
-    config_dir: String,
-    lang: String,
-    conf: &Config,
-    usedb: bool,
-) -> Result<Vec<Vocab>, VctError> {
-    if usedb {
-        let mut path: String = match &conf.dbpath {
-            Some(n) => n.to_string(),
-            None => String::from("db.sqlite"),
-        };
-        if !path.starts_with('/') {
-            path = format!("{}/{}", config_dir, path);
-        }
-        let db = match Connection::open(path.as_str()) {
-            Ok(n) => n,
-            Err(_) => {
-                return Err(VctError::new(
-                    VctErrorKind::DatabaseError,
-                    "error connecting to database",
-                ));
-            }
-        };
-        match db.execute("CREATE TABLE IF NOT EXISTS vocab (lang VARCHAR(256) NOT NULL, name VARCHAR(256) NOT NULL, meanings VARCHAR(256) NOT NULL, additionals VARCHAR(256))", []) {
-            Ok(_) => (),
-            Err(_) => {
-                return Err(VctError::new(VctErrorKind::DatabaseError, "error while creating database"));
-            }
-        }
+/// an additional as it is serialized
+#[derive(Serialize)]
+struct Additional<'a> {
+    key: &'a str,
+    value: &'a str,
+}
 
-        let mut sel = match db.prepare(
-            format!(
-                "SELECT name, meanings, additionals FROM vocab WHERE (lang == '{}')",
-                lang
-            )
-            .as_str(),
-        ) {
-            Ok(n) => n,
-            Err(_) => {
-                return Err(VctError::new(VctErrorKind::DatabaseError, "problem with the language provided and the database. Maybe your vocab is in a dict file? Try `--nodb` to disable the database"));
-            }
-        };
-        let vocab_iter = sel.query_map([], |row| {
-            let out: Vocab;
-            let name: Result<String, rusqlite::Error> = row.get(0);
-            let meanings: Result<String, rusqlite::Error> = row.get(1);
-            let additionals: Result<Option<String>, rusqlite::Error> = row.get(2);
-            if let Some(adds) = additionals.unwrap() {
-                out = match Vocab::from_string(format!(
-                    "{};{};{}",
-                    name.unwrap(),
-                    meanings.unwrap(),
-                    adds
-                )) {
-                    Ok(n) => n,
-                    Err(_) => {
-                        return Err(rusqlite::Error::ExecuteReturnedResults);
-                    }
-                };
-            } else {
-                out = match Vocab::from_string(format!("{};{}", name.unwrap(), meanings.unwrap())) {
-                    Ok(n) => n,
-                    Err(_) => Vocab::new(String::new(), Vec::new(), None),
-                };
-            }
-            Ok(out)
-        });
-        let vocab: Vec<Vocab> = vocab_iter.unwrap().map(|x| x.unwrap()).collect();
-        Ok(vocab)
-    } else {
-        let mut dict_dirname: String = format!("{}/dicts", config_dir);
-        if conf.dict != None {
-            let dict = conf.dict.as_ref().unwrap();
-            if dict.is_empty() {
-                info::print_info(
-                    &btui::Terminal::new(),
-                    "dict in config is empty. Ignoring...",
-                    info::MessageType::Warning,
-                );
-            } else if dict.starts_with('/') {
-                dict_dirname = dict.clone();
-            } else {
-                dict_dirname = format!("{}/{}", config_dir, dict.clone());
-            }
+impl Serialize for Vocab {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let additionals: Vec<Additional> = self
+            .additionals
+            .iter()
+            .flatten()
+            .map(|x| {
+                let (key, value) = x.split_once(':').unwrap_or((x.as_str(), ""));
+                Additional { key, value }
+            })
+            .collect();
+        let mut out = serializer.serialize_struct("Vocab", 4)?;
+        out.serialize_field("name", &self.name)?;
+        out.serialize_field("meanings", &self.meanings)?;
+        out.serialize_field("additionals", &additionals)?;
+        out.serialize_field("tags", &self.tags)?;
+        out.end()
+    }
+}
+
+/// an invalid line of a dict file
+#[derive(Debug, Clone)]
+pub struct LineError {
+    pub path: String,
+    /// the line starting at 1
+    pub line: usize,
+    /// the column (in characters) starting at 1
+    pub column: usize,
+    pub reason: String,
+}
+
+impl fmt::Display for LineError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}:{}:{}: {}",
+            self.path, self.line, self.column, self.reason
+        )
+    }
+}
+
+/// check whether a line of a dict file contains no vocab (it is blank or a comment starting with
+/// `#`)
+/// # Arguments
+/// * `line`: the line to check
+pub fn is_ignored(line: &str) -> bool {
+    let line: &str = line.trim_start();
+    line.is_empty() || line.starts_with('#')
+}
+
+/// parse the contents of a dict file. Blank lines and comments are skipped
+/// # Arguments
+/// * `path`: the path of the file (used for the errors)
+/// * `contents`: the contents of the file
+/// # Returns
+/// the line and vocab of every valid line and the errors of the invalid ones
+pub fn parse_dict(path: &str, contents: &str) -> (Vec<(usize, Vocab)>, Vec<LineError>) {
+    let mut vocab: Vec<(usize, Vocab)> = Vec::new();
+    let mut errors: Vec<LineError> = Vec::new();
+    for (idx, line) in contents.lines().enumerate() {
+        if is_ignored(line) {
+            continue;
         }
-        let conf_contents: String =
-            match read_to_string(format!("{}/{}", dict_dirname, lang).as_str()) {
-                Ok(n) => n,
-                Err(_) => {
-                    return Err(VctError::new(
-                        VctErrorKind::FileError,
-                        "problem opening dictionary file",
-                    ));
-                }
-            };
-        let mut out: Vec<Vocab> = Vec::new();
-        for line in conf_contents.as_str().lines() {
-            let new_line: String = line.chars().filter(|x| x != &'\n').collect();
-            let voc: Vocab = match Vocab::from_string(new_line) {
-                Ok(n) => n,
-                Err(e) => {
-                    return Err(e);
-                }
-            };
-            out.push(voc);
+        match Vocab::parse(line) {
+            Ok(n) => vocab.push((idx + 1, n)),
+            Err((column, reason)) => errors.push(LineError {
+                path: path.to_string(),
+                line: idx + 1,
+                column,
+                reason: reason.to_string(),
+            }),
         }
+    }
+    (vocab, errors)
+}
+
+/// create a vocab from the columns of a row in the vocab table of older versions
+/// # Arguments
+/// * `name`: the name column
+/// * `meanings`: the meanings column
+/// * `additionals`: the additionals column
+/// * `tags`: the tags column
+pub fn vocab_from_row(
+    name: String,
+    meanings: String,
+    additionals: Option<String>,
+    tags: Option<String>,
+) -> Result<Vocab, VctError> {
+    Vocab::from_string(format!(
+        "{};{};{};{}",
+        name,
+        meanings,
+        additionals.unwrap_or_default(),
+        tags.unwrap_or_default()
+    ))
+}
 
-        Ok(out)
+/// escape backslashes and the given seperators with a backslash
+/// # Arguments
+/// * `value`: the text to escape
+/// * `seperators`: the seperators that would end the text at its position in a dict line
+pub fn escape(value: &str, seperators: &[char]) -> String {
+    let mut out: String = String::with_capacity(value.len());
+    for c in value.chars() {
+        if c == '\\' || seperators.contains(&c) {
+            out.push('\\');
+        }
+        out.push(c);
     }
+    out
 }
 
-// TODO: add write functionality
+/// escape the items of a list and join them with commas
+/// # Arguments
+/// * `items`: the items of the list
+pub fn escape_list(items: &[String]) -> String {
+    items
+        .iter()
+        .map(|x| escape(x, &[';', ',']))
+        .collect::<Vec<String>>()
+        .join(",")
+}
 
-pub fn write_vocab(
-    file: &str,
-    lang: &str,
-    vocab: Vocab,
-    term: &btui::Terminal,
-    db: bool,
-) -> Result<()> {
-    if db {
-        let conn = match Connection::open(file) {
-            Ok(n) => n,
-            Err(e) => {
-                info::print_info(
-                    term,
-                    format!("error opening connection to database: {}", e),
-                    info::MessageType::Error,
-                );
-                exit(1);
+/// check whether a character can be escaped. A backslash in front of any other character is kept
+/// as it is so files written before escaping existed are read unchanged
+fn escapable(c: char) -> bool {
+    matches!(c, '\\' | ';' | ',' | ':' | '#')
+}
+
+/// remove the escaping backslashes of a text
+/// # Arguments
+/// * `value`: the escaped text
+pub fn unescape(value: &str) -> String {
+    let mut out: String = String::with_capacity(value.len());
+    let mut chars = value.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (c, chars.peek()) {
+            ('\\', Some(&n)) if escapable(n) => {
+                out.push(n);
+                chars.next();
             }
-        };
+            _ => out.push(c),
+        }
+    }
+    out
+}
 
-        match conn.execute("CREATE TABLE IF NOT EXISTS vocab (lang VARCHAR(256) NOT NULL, name VARCHAR(256) NOT NULL, meanings VARCHAR(256) NOT NULL, additionals VARCHAR(256))", []) {
-            Ok(_) => (),
-            Err(e) => {
-                info::print_info(term, format!("error preparing database: {}", e), info::MessageType::Error);
-                exit(1);
+/// split a text at every seperator that isn't escaped. The parts are still escaped
+/// # Arguments
+/// * `value`: the escaped text
+/// * `seperator`: the seperator to split at
+pub fn split_unescaped(value: &str, seperator: char) -> Vec<String> {
+    let mut out: Vec<String> = vec![String::new()];
+    let mut chars = value.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (c, chars.peek()) {
+            ('\\', Some(&n)) if escapable(n) => {
+                let last: &mut String = out.last_mut().unwrap();
+                last.push(c);
+                last.push(n);
+                chars.next();
             }
+            (c, _) if c == seperator => out.push(String::new()),
+            (c, _) => out.last_mut().unwrap().push(c),
         }
+    }
+    out
+}
 
-        if let Some(n) = vocab.additionals {
-            let mut adds: String = n.iter().map(|x| format!("{},", x)).collect();
-            adds = adds[..adds.len() - 1].to_string();
-            let mut meanings: String = vocab.meanings.iter().map(|x| format!("{},", x)).collect();
-            meanings = meanings[..meanings.len() - 1].to_string();
-            match conn.execute(
-                "INSERT INTO vocab (lang, name, meanings, additionals) VALUES (?, ?, ?, ?)",
-                params![lang, vocab.name, meanings, adds],
-            ) {
-                Ok(_) => (),
-                Err(e) => {
-                    info::print_info(
-                        term,
-                        format!("error inserting into database: {}", e),
-                        info::MessageType::Error,
-                    );
-                    exit(1);
-                }
+/// split a text at the first seperator that isn't escaped
+fn split_once_unescaped(value: &str, seperator: char) -> Option<(&str, &str)> {
+    let mut chars = value.char_indices().peekable();
+    while let Some((idx, c)) = chars.next() {
+        match (c, chars.peek()) {
+            ('\\', Some(&(_, n))) if escapable(n) => {
+                chars.next();
             }
-        } else {
-            let mut meanings: String = vocab.meanings.iter().map(|x| format!("{},", x)).collect();
-            meanings = meanings[..meanings.len() - 1].to_string();
-            match conn.execute(
-                "INSERT INTO vocab (lang, name, meanings, additionals) VALUES (?, ?, ?, NULL)",
-                params![lang, vocab.name, meanings],
-            ) {
-                Ok(_) => (),
-                Err(e) => {
-                    info::print_info(
-                        term,
-                        format!("error inserting into database: {}", e),
-                        info::MessageType::Error,
-                    );
-                    exit(1);
-                }
+            (c, _) if c == seperator => {
+                return Some((&value[..idx], &value[idx + c.len_utf8()..]));
             }
+            _ => {}
         }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-        return Ok(());
+    fn strings(values: &[&str]) -> Vec<String> {
+        values.iter().map(|x| x.to_string()).collect()
     }
-    if !Path::new(file).exists() {
-        if file.contains('/') {
-            let p = Path::new(file);
-            let parent = p.parent().unwrap();
-            if !parent.exists() {
-                match create_dir_all(parent.to_str().unwrap()) {
-                    Ok(_) => (),
-                    Err(e) => {
-                        info::print_info(
-                            term,
-                            format!("failed creating necessary directories: {}", e),
-                            info::MessageType::Error,
-                        );
-                        exit(1);
-                    }
-                }
-            }
-        }
-        let mut fhandle = match OpenOptions::new().append(true).open(file) {
-            Ok(n) => n,
-            Err(e) => {
-                info::print_info(
-                    term,
-                    format!("error opening dictionary file: {}", e),
-                    info::MessageType::Error,
-                );
-                exit(1);
-            }
-        };
-        let mut meanings: String = vocab.meanings.iter().map(|x| format!("{},", x)).collect();
-        meanings = meanings[..meanings.len() - 1].to_string();
-        if let Some(adds) = vocab.additionals {
-            let mut additionals: String = adds.iter().map(|x| format!("{},", x)).collect();
-            additionals = additionals[..additionals.len() - 1].to_string();
-            match fhandle.write_all(
-                format!("{};{};{}", vocab.name, meanings, additionals)
-                    .as_str()
-                    .as_bytes(),
-            ) {
-                Ok(_) => (),
-                Err(e) => {
-                    info::print_info(
-                        term,
-                        format!("error writing to file: {}", e),
-                        info::MessageType::Error,
-                    );
-                    exit(1);
-                }
-            }
-        } else {
-            match fhandle.write_all(format!("{};{}", vocab.name, meanings).as_str().as_bytes()) {
-                Ok(_) => (),
-                Err(e) => {
-                    info::print_info(
-                        term,
-                        format!("error writing to file: {}", e),
-                        info::MessageType::Error,
-                    );
-                    exit(1);
-                }
-            }
+
+    #[test]
+    fn escape_round_trip() {
+        for value in [
+            "plain",
+            "a;b",
+            "a,b",
+            "key:value",
+            "#1",
+            "\\",
+            "a\\",
+            "a\\;b",
+            ";,:#\\",
+        ] {
+            assert_eq!(unescape(&escape(value, &[';', ',', ':', '#'])), value);
         }
+        assert_eq!(escape("a;b,c\\", &[';']), "a\\;b,c\\\\");
+    }
+
+    #[test]
+    fn unknown_escapes_are_kept() {
+        assert_eq!(unescape("C:\\new"), "C:\\new");
+        // a backslash at the end escapes nothing
+        assert_eq!(unescape("dir\\"), "dir\\");
+        assert_eq!(split_unescaped("dir\\", ','), strings(&["dir\\"]));
+    }
+
+    #[test]
+    fn split_at_unescaped_seperators() {
+        assert_eq!(
+            split_unescaped("a,b\\,c,,d\\\\,e", ','),
+            strings(&["a", "b\\,c", "", "d\\\\", "e"])
+        );
+        assert_eq!(split_unescaped("", ','), strings(&[""]));
+        assert_eq!(
+            split_once_unescaped("a\\:b:c:d", ':'),
+            Some(("a\\:b", "c:d"))
+        );
+        assert_eq!(split_once_unescaped("a\\:b", ':'), None);
     }
 
-    Ok(())
+    #[test]
+    fn line_round_trip() {
+        let vocab: Vec<Vocab> = vec![
+            Vocab::new(
+                String::from("# not a comment"),
+                strings(&["a; b", "c, d"]),
+                Some(strings(&["note:a:b, c", "path:C:\\dir\\"])),
+                strings(&["x,y"]),
+            ),
+            Vocab::new(
+                String::from("back\\"),
+                strings(&["slash\\"]),
+                None,
+                strings(&["tag\\"]),
+            ),
+            Vocab::new(
+                String::from("  #indented"),
+                strings(&["x"]),
+                None,
+                Vec::new(),
+            ),
+        ];
+        for voc in &vocab {
+            let line: String = voc.to_line();
+            assert!(!is_ignored(&line), "{}", line);
+            assert_eq!(&Vocab::from_string(line.clone()).unwrap(), voc, "{}", line);
+            assert!(voc.validate().is_ok());
+        }
+        let contents: String = vocab.iter().map(|x| x.to_line() + "\n").collect::<String>();
+        let (parsed, errors) = parse_dict("test", &contents);
+        assert!(errors.is_empty());
+        assert_eq!(
+            parsed.into_iter().map(|(_, x)| x).collect::<Vec<Vocab>>(),
+            vocab
+        );
+    }
 }
-// TODO: add a function to load all vocabulary from dictionaries
@@ -0,0 +1,162 @@
+use crate::dict::{split_unescaped, unescape, Vocab};
+use crate::error::*;
+use crate::storage::{Entry, Storage};
+use btui::effects::{Color, Special};
+use btui::print::{fg, sp};
+
+/// the fields of a vocab that can be edited
+pub const FIELDS: [&str; 4] = ["name", "meanings", "additionals", "tags"];
+
+/// find the entry of a language addressed by its name or by `@<id>` (only if the ids of the storage
+/// are stable)
+/// # Arguments
+/// * `storage`: the storage containing the language
+/// * `lang`: the language
+/// * `key`: the name of the vocab or `@` followed by its id
+pub fn find(storage: &dyn Storage, lang: &str, key: &str) -> Result<Entry, VctError> {
+    if key.starts_with('@') && !storage.stable_ids() {
+        return Err(VctError::new(
+            VctErrorKind::ParamError,
+            "vocabulary in dict files can't be addressed by @<id> because their lines change when \
+             the file is edited. Use the name or the database (--db)",
+        ));
+    }
+    let entries: Vec<Entry> = storage.load(lang)?;
+    if let Some(n) = key.strip_prefix('@') {
+        let id: i64 = match n.parse::<i64>() {
+            Ok(i) => i,
+            Err(_) => {
+                return Err(VctError::new(
+                    VctErrorKind::ParamError,
+                    format!("'{}' is not a valid id", n).as_str(),
+                ));
+            }
+        };
+        return match entries.into_iter().find(|x| x.id == id) {
+            Some(e) => Ok(e),
+            None => Err(VctError::new(
+                VctErrorKind::ParamError,
+                format!("{} has no vocab with id {}", lang, id).as_str(),
+            )),
+        };
+    }
+    let mut found: Vec<Entry> = entries
+        .into_iter()
+        .filter(|x| x.vocab.get_name() == key)
+        .collect();
+    match found.len() {
+        0 => Err(VctError::new(
+            VctErrorKind::ParamError,
+            format!("{} has no vocab '{}'", lang, key).as_str(),
+        )),
+        1 => Ok(found.remove(0)),
+        _ if storage.stable_ids() => Err(VctError::new(
+            VctErrorKind::ParamError,
+            format!(
+                "'{}' is ambiguous in {} (ids {}). Use @<id> instead",
+                key,
+                lang,
+                found
+                    .iter()
+                    .map(|x| x.id.to_string())
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            )
+            .as_str(),
+        )),
+        _ => Err(VctError::new(
+            VctErrorKind::ParamError,
+            format!(
+                "'{}' is ambiguous in {} ({}). Merge the duplicates with vct dedupe first",
+                key,
+                lang,
+                found
+                    .iter()
+                    .map(|x| storage.location(lang, x.id))
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            )
+            .as_str(),
+        )),
+    }
+}
+
+/// split a comma seperated value into its trimmed, non-empty parts. Commas can be escaped like in
+/// a dict file
+pub fn split_list(value: &str) -> Vec<String> {
+    split_unescaped(value, ',')
+        .iter()
+        .map(|x| unescape(x).trim().to_string())
+        .filter(|x| !x.is_empty())
+        .collect()
+}
+
+/// change fields of a vocab
+/// # Arguments
+/// * `vocab`: the vocab to change
+/// * `changes`: pairs of a field (`name`, `meanings`, `additionals` or `tags`) and its new value.
+///   Lists are comma seperated (`\,` for a comma inside an item) and additionals `key:value`
+///   pairs
+/// # Returns
+/// the changed vocab if it is valid
+pub fn set_fields(vocab: &Vocab, changes: &[String]) -> Result<Vocab, VctError> {
+    if changes.is_empty() || !changes.len().is_multiple_of(2) {
+        return Err(VctError::new(
+            VctErrorKind::ParamError,
+            "expected pairs of a field and its new value",
+        ));
+    }
+    let mut name: String = vocab.get_name();
+    let mut meanings: Vec<String> = vocab.get_meanings();
+    let mut additionals: Option<Vec<String>> = vocab.get_additionals();
+    let mut tags: Vec<String> = vocab.get_tags();
+    for pair in changes.chunks(2) {
+        let value: &str = pair[1].as_str();
+        match pair[0].as_str() {
+            "name" => name = value.trim().to_string(),
+            "meanings" => meanings = split_list(value),
+            "additionals" => {
+                let adds: Vec<String> = split_list(value);
+                additionals = if adds.is_empty() { None } else { Some(adds) };
+            }
+            "tags" => tags = split_list(value),
+            n => {
+                return Err(VctError::new(
+                    VctErrorKind::ParamError,
+                    format!("'{}' is not a field. Valid are {}", n, FIELDS.join(", ")).as_str(),
+                ));
+            }
+        }
+    }
+    let out: Vocab = Vocab::new(name, meanings, additionals, tags);
+    out.validate()?;
+    Ok(out)
+}
+
+/// format a confirmation showing a vocab before and after a change
+/// # Arguments
+/// * `before`: a label (e.g. the language and id) and the vocab before the change (`None` if it
+///   was added)
+/// * `after`: a label and the vocab after the change (`None` if it was removed)
+pub fn confirmation(before: Option<(&str, &Vocab)>, after: Option<(&str, &Vocab)>) -> String {
+    let mut out: Vec<String> = Vec::new();
+    if let Some((label, voc)) = before {
+        out.push(format!(
+            "{}- {}: {}{}",
+            fg(Color::Red),
+            label,
+            voc.to_line(),
+            sp(Special::Reset)
+        ));
+    }
+    if let Some((label, voc)) = after {
+        out.push(format!(
+            "{}+ {}: {}{}",
+            fg(Color::Green),
+            label,
+            voc.to_line(),
+            sp(Special::Reset)
+        ));
+    }
+    out.join("\n")
+}
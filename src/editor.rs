@@ -0,0 +1,603 @@
+use crate::dict::{escape, Vocab};
+use crate::edit::{set_fields, FIELDS};
+use crate::error::*;
+use crate::pretty_print::pretty_print;
+use crate::query::{language_names, Query};
+use crate::storage::{Entry, MemoryStorage, Storage};
+use btui::effects::{Color, Special};
+use btui::print::{fg, sp};
+use btui::Terminal;
+use std::io::{Read, Write};
+use std::process::{Command, Stdio};
+
+/// a key pressed by the user
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Key {
+    Char(char),
+    Ctrl(char),
+    Up,
+    Down,
+    Left,
+    Right,
+    PageUp,
+    PageDown,
+    Home,
+    End,
+    Enter,
+    Tab,
+    BackTab,
+    Backspace,
+    Delete,
+    Esc,
+}
+
+/// puts the terminal into raw mode while it exists and restores the previous settings when dropped
+struct RawMode {
+    saved: String,
+}
+
+/// run stty on the terminal of stdin
+fn stty(args: &[&str]) -> Option<String> {
+    let out = Command::new("stty")
+        .args(args)
+        .stdin(Stdio::inherit())
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+    if !out.status.success() {
+        return None;
+    }
+    Some(String::from_utf8_lossy(&out.stdout).trim().to_string())
+}
+
+impl RawMode {
+    fn enable() -> Result<RawMode, VctError> {
+        let saved: String = match stty(&["-g"]) {
+            Some(n) => n,
+            None => {
+                return Err(VctError::new(
+                    VctErrorKind::ParamError,
+                    "the editor needs a terminal",
+                ));
+            }
+        };
+        // `min 0 time 1` makes reads return after 0.1s without input so a lone escape can be told
+        // apart from an escape sequence
+        if stty(&["-icanon", "-echo", "-ixon", "min", "0", "time", "1"]).is_none() {
+            return Err(VctError::new(
+                VctErrorKind::ParamError,
+                "the editor needs a terminal",
+            ));
+        }
+        Ok(RawMode { saved })
+    }
+}
+
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        stty(&[self.saved.as_str()]);
+    }
+}
+
+/// get the amount of rows of the terminal
+fn rows() -> usize {
+    stty(&["size"])
+        .and_then(|x| x.split_whitespace().next().and_then(|n| n.parse().ok()))
+        .filter(|x: &usize| *x > 0)
+        .unwrap_or(24)
+}
+
+/// read a byte from stdin or `None` if there was no input in time
+fn read_byte() -> Option<u8> {
+    let mut buf: [u8; 1] = [0];
+    match std::io::stdin().lock().read(&mut buf) {
+        Ok(1) => Some(buf[0]),
+        _ => None,
+    }
+}
+
+/// wait for the next key pressed
+fn read_key() -> Key {
+    let first: u8 = loop {
+        if let Some(n) = read_byte() {
+            break n;
+        }
+    };
+    match first {
+        b'\r' | b'\n' => Key::Enter,
+        b'\t' => Key::Tab,
+        0x7f | 0x08 => Key::Backspace,
+        0x1b => read_escape(),
+        1..=26 => Key::Ctrl((b'a' + first - 1) as char),
+        n if n < 0x80 => Key::Char(n as char),
+        n => {
+            // collect the continuation bytes of an utf-8 character
+            let len: usize = match n {
+                0xc0..=0xdf => 2,
+                0xe0..=0xef => 3,
+                _ => 4,
+            };
+            let mut bytes: Vec<u8> = vec![n];
+            while bytes.len() < len {
+                match read_byte() {
+                    Some(b) => bytes.push(b),
+                    None => break,
+                }
+            }
+            match String::from_utf8(bytes).ok().and_then(|x| x.chars().next()) {
+                Some(c) => Key::Char(c),
+                None => read_key(),
+            }
+        }
+    }
+}
+
+/// read the rest of an escape sequence
+fn read_escape() -> Key {
+    match read_byte() {
+        Some(b'[') | Some(b'O') => {}
+        _ => return Key::Esc,
+    }
+    let mut seq: String = String::new();
+    while let Some(b) = read_byte() {
+        seq.push(b as char);
+        if b.is_ascii_alphabetic() || b == b'~' {
+            break;
+        }
+    }
+    match seq.as_str() {
+        "A" => Key::Up,
+        "B" => Key::Down,
+        "C" => Key::Right,
+        "D" => Key::Left,
+        "H" | "1~" | "7~" => Key::Home,
+        "F" | "4~" | "8~" => Key::End,
+        "Z" => Key::BackTab,
+        "3~" => Key::Delete,
+        "5~" => Key::PageUp,
+        "6~" => Key::PageDown,
+        _ => Key::Esc,
+    }
+}
+
+/// a vocab being edited as one text per field
+struct Form {
+    /// the id of the edited entry or `None` if it is a new one
+    id: Option<i64>,
+    fields: [Vec<char>; 4],
+    field: usize,
+    cursor: usize,
+    error: Option<String>,
+}
+
+impl Form {
+    fn new(entry: Option<&Entry>) -> Form {
+        // lists are shown the way `edit::set_fields` reads them
+        let list = |items: Vec<String>| -> String {
+            items
+                .iter()
+                .map(|x| escape(x, &[',']))
+                .collect::<Vec<String>>()
+                .join(", ")
+        };
+        let values: [String; 4] = match entry {
+            Some(n) => [
+                n.vocab.get_name(),
+                list(n.vocab.get_meanings()),
+                list(n.vocab.get_additionals().unwrap_or_default()),
+                list(n.vocab.get_tags()),
+            ],
+            None => Default::default(),
+        };
+        let fields: [Vec<char>; 4] = values.map(|x| x.chars().collect());
+        let cursor: usize = fields[0].len();
+        Form {
+            id: entry.map(|x| x.id),
+            fields,
+            field: 0,
+            cursor,
+            error: None,
+        }
+    }
+
+    fn select(&mut self, field: usize) {
+        self.field = field;
+        self.cursor = self.fields[field].len();
+    }
+
+    /// build the vocab the same way `vct edit` changes it
+    fn vocab(&self) -> Result<Vocab, VctError> {
+        let changes: Vec<String> = FIELDS
+            .iter()
+            .zip(self.fields.iter())
+            .flat_map(|(field, value)| [field.to_string(), value.iter().collect()])
+            .collect();
+        set_fields(
+            &Vocab::new(String::new(), Vec::new(), None, Vec::new()),
+            &changes,
+        )
+    }
+}
+
+/// what the editor is currently doing
+enum Mode {
+    Browse,
+    Filter,
+    Edit(Form),
+}
+
+/// state of the editor
+struct Editor {
+    lang: String,
+    /// the entries as they are in the storage
+    original: Vec<Entry>,
+    /// the entries including the unsaved changes
+    working: MemoryStorage,
+    filter: String,
+    /// the last valid query typed as filter
+    query: Query,
+    /// the names the language can be found with in a query
+    names: Vec<String>,
+    selected: usize,
+    top: usize,
+    mode: Mode,
+    status: String,
+    /// whether quitting was requested once although there are unsaved changes
+    quitting: bool,
+}
+
+impl Editor {
+    fn entries(&self) -> Vec<Entry> {
+        self.working.load(self.lang.as_str()).unwrap_or_default()
+    }
+
+    /// the entries matching the filter
+    fn visible(&self) -> Vec<Entry> {
+        self.entries()
+            .into_iter()
+            .filter(|x| self.query.matches(&self.names, &x.vocab))
+            .collect()
+    }
+
+    fn modified(&self) -> bool {
+        self.entries() != self.original
+    }
+
+    fn render(&mut self, term: &Terminal) {
+        let height: usize = rows();
+        let mut out: Vec<String> = Vec::new();
+        out.push(format!(
+            "{}{}{}{}{}",
+            sp(Special::Bold),
+            self.lang,
+            if self.modified() { " [modified]" } else { "" },
+            sp(Special::Reset),
+            match &self.mode {
+                Mode::Filter => format!("  filter: {}_", self.filter),
+                _ if !self.filter.is_empty() => format!("  filter: {}", self.filter),
+                _ => String::new(),
+            }
+        ));
+        match &self.mode {
+            Mode::Edit(form) => {
+                out.push(String::from(if form.id.is_some() {
+                    "editing vocab"
+                } else {
+                    "new vocab"
+                }));
+                out.push(String::new());
+                for (n, field) in FIELDS.iter().enumerate() {
+                    let mut value: String = form.fields[n].iter().collect();
+                    if n == form.field {
+                        let before: String = form.fields[n][..form.cursor].iter().collect();
+                        let after: String = form.fields[n][form.cursor..].iter().collect();
+                        value = format!(
+                            "{}{}|{}{}",
+                            before,
+                            sp(Special::Bold),
+                            sp(Special::Reset),
+                            after
+                        );
+                    }
+                    out.push(format!(
+                        "{}{:>12}{}: {}",
+                        if n == form.field {
+                            fg(Color::Green)
+                        } else {
+                            fg(Color::White)
+                        },
+                        field,
+                        sp(Special::Reset),
+                        value
+                    ));
+                }
+                out.push(String::new());
+                if let Some(n) = &form.error {
+                    out.push(format!("{}{}{}", fg(Color::Red), n, sp(Special::Reset)));
+                }
+                out.push(String::from(
+                    "lists are comma seperated (\\, for a comma inside an item), additionals are key:value pairs",
+                ));
+                out.push(String::from(
+                    "tab/arrows: switch field  enter: apply  esc: cancel",
+                ));
+            }
+            _ => {
+                let visible: Vec<Entry> = self.visible();
+                self.selected = self.selected.min(visible.len().saturating_sub(1));
+                // the lines of every entry so the selected one can be kept on screen
+                let blocks: Vec<Vec<String>> = visible
+                    .iter()
+                    .map(|x| {
+                        pretty_print(vec![x.vocab.clone()])
+                            .lines()
+                            .filter(|l| !l.is_empty())
+                            .map(|l| l.to_string())
+                            .collect()
+                    })
+                    .collect();
+                let space: usize = height.saturating_sub(4).max(1);
+                if self.selected < self.top {
+                    self.top = self.selected;
+                }
+                while self.top < self.selected
+                    && blocks[self.top..=self.selected]
+                        .iter()
+                        .map(|x| x.len())
+                        .sum::<usize>()
+                        > space
+                {
+                    self.top += 1;
+                }
+                let mut list: Vec<String> = Vec::new();
+                for (n, block) in blocks.iter().enumerate().skip(self.top) {
+                    for (i, line) in block.iter().enumerate() {
+                        let marker: &str = if n == self.selected && i == 0 {
+                            "> "
+                        } else {
+                            "  "
+                        };
+                        list.push(format!("{}{}", marker, line));
+                    }
+                    if list.len() >= space {
+                        break;
+                    }
+                }
+                list.truncate(space);
+                if visible.is_empty() {
+                    list.push(String::from("  no vocab"));
+                }
+                list.resize(space, String::new());
+                out.append(&mut list);
+                out.push(self.status.clone());
+                out.push(String::from(match self.mode {
+                    Mode::Filter => "type to filter  enter/esc: done",
+                    _ => "j/k: move  /: filter  e: edit  a: add  d: delete  s: save  q: quit",
+                }));
+            }
+        }
+        term.clear_screen().unwrap();
+        term.set_cursor(1, 1).unwrap();
+        term.print(format!("{}{}", out.join("\n"), sp(Special::Reset)))
+            .unwrap();
+        // the last line has no newline so stdout has to be flushed
+        std::io::stdout().flush().unwrap();
+    }
+
+    /// handle a key in the list
+    /// # Returns
+    /// whether the editor should be closed
+    fn browse(
+        &mut self,
+        key: Key,
+        storage: &mut dyn Storage,
+        changes: &mut Vec<(String, Option<String>)>,
+    ) -> bool {
+        let amount: usize = self.visible().len();
+        if key != Key::Char('q') {
+            self.quitting = false;
+        }
+        match key {
+            Key::Up | Key::Char('k') => self.selected = self.selected.saturating_sub(1),
+            Key::Down | Key::Char('j') => {
+                self.selected = (self.selected + 1).min(amount.saturating_sub(1))
+            }
+            Key::PageUp => self.selected = self.selected.saturating_sub(10),
+            Key::PageDown => self.selected = (self.selected + 10).min(amount.saturating_sub(1)),
+            Key::Home | Key::Char('g') => self.selected = 0,
+            Key::End | Key::Char('G') => self.selected = amount.saturating_sub(1),
+            Key::Char('/') => self.mode = Mode::Filter,
+            Key::Esc => {
+                self.filter.clear();
+                self.query = Query::parse("").unwrap();
+            }
+            Key::Enter | Key::Char('e') => {
+                if let Some(n) = self.visible().get(self.selected) {
+                    self.mode = Mode::Edit(Form::new(Some(n)));
+                }
+            }
+            Key::Char('a') => self.mode = Mode::Edit(Form::new(None)),
+            Key::Char('d') => {
+                if let Some(n) = self.visible().get(self.selected) {
+                    self.working.delete(self.lang.as_str(), n.id).unwrap();
+                    self.status = format!("deleted '{}'", n.vocab.get_name());
+                }
+            }
+            Key::Char('s') | Key::Ctrl('s') => match self.save(storage, changes) {
+                Ok(0) => self.status = String::from("nothing to save"),
+                Ok(n) => self.status = format!("saved {} changes", n),
+                Err(e) => self.status = format!("{}{}{}", fg(Color::Red), e, sp(Special::Reset)),
+            },
+            Key::Char('q') | Key::Ctrl('c') => {
+                if !self.modified() || self.quitting || key == Key::Ctrl('c') {
+                    return true;
+                }
+                self.quitting = true;
+                self.status =
+                    String::from("there are unsaved changes. Press q again to discard them");
+            }
+            _ => {}
+        }
+        false
+    }
+
+    /// handle a key while typing the filter
+    fn type_filter(&mut self, key: Key) {
+        match key {
+            Key::Enter | Key::Esc => self.mode = Mode::Browse,
+            Key::Backspace => {
+                self.filter.pop();
+            }
+            Key::Char(c) => self.filter.push(c),
+            _ => {}
+        }
+        // unfinished queries keep the previous filter until they are valid
+        match Query::parse(self.filter.as_str()) {
+            Ok(n) => {
+                self.query = n;
+                self.status = String::new();
+            }
+            Err(e) => self.status = e.to_string(),
+        }
+        self.selected = 0;
+        self.top = 0;
+    }
+
+    /// handle a key in the form
+    fn edit(&mut self, key: Key) {
+        let form: &mut Form = match &mut self.mode {
+            Mode::Edit(n) => n,
+            _ => return,
+        };
+        let len: usize = form.fields[form.field].len();
+        match key {
+            Key::Esc => self.mode = Mode::Browse,
+            Key::Tab | Key::Down => form.select((form.field + 1) % FIELDS.len()),
+            Key::BackTab | Key::Up => form.select((form.field + FIELDS.len() - 1) % FIELDS.len()),
+            Key::Left => form.cursor = form.cursor.saturating_sub(1),
+            Key::Right => form.cursor = (form.cursor + 1).min(len),
+            Key::Home | Key::Ctrl('a') => form.cursor = 0,
+            Key::End | Key::Ctrl('e') => form.cursor = len,
+            Key::Backspace if form.cursor > 0 => {
+                form.cursor -= 1;
+                form.fields[form.field].remove(form.cursor);
+            }
+            Key::Delete if form.cursor < len => {
+                form.fields[form.field].remove(form.cursor);
+            }
+            Key::Char(c) => {
+                form.fields[form.field].insert(form.cursor, c);
+                form.cursor += 1;
+            }
+            Key::Enter => match form.vocab() {
+                Ok(voc) => {
+                    let lang: &str = self.lang.as_str();
+                    match form.id {
+                        Some(id) => self.working.update(lang, id, &voc).unwrap(),
+                        None => {
+                            self.working.insert(lang, &voc).unwrap();
+                        }
+                    }
+                    self.status = format!("changed '{}' (not saved yet)", voc.get_name());
+                    self.mode = Mode::Browse;
+                }
+                Err(e) => form.error = Some(e.to_string()),
+            },
+            _ => {}
+        }
+    }
+
+    /// write the changes to the storage
+    /// # Returns
+    /// the amount of changed vocab
+    fn save(
+        &mut self,
+        storage: &mut dyn Storage,
+        changes: &mut Vec<(String, Option<String>)>,
+    ) -> Result<usize, VctError> {
+        let lang: &str = self.lang.as_str();
+        let entries: Vec<Entry> = self.entries();
+        let mut count: usize = 0;
+        // ids of dict files are line numbers, so update before deleting and delete from the end
+        for old in &self.original {
+            if let Some(new) = entries.iter().find(|x| x.id == old.id) {
+                if new.vocab != old.vocab {
+                    storage.update(lang, old.id, &new.vocab)?;
+                    changes.push((old.vocab.get_name(), Some(new.vocab.get_name())));
+                    count += 1;
+                }
+            }
+        }
+        let mut removed: Vec<&Entry> = self
+            .original
+            .iter()
+            .filter(|x| !entries.iter().any(|n| n.id == x.id))
+            .collect();
+        removed.sort_by_key(|x| std::cmp::Reverse(x.id));
+        for old in removed {
+            storage.delete(lang, old.id)?;
+            changes.push((old.vocab.get_name(), None));
+            count += 1;
+        }
+        for new in entries
+            .iter()
+            .filter(|x| !self.original.iter().any(|n| n.id == x.id))
+        {
+            storage.insert(lang, &new.vocab)?;
+            count += 1;
+        }
+        // reload as the ids may have changed
+        self.working = MemoryStorage::copy_of(&*storage, std::slice::from_ref(&self.lang))?;
+        self.original = self.entries();
+        Ok(count)
+    }
+}
+
+/// edit a language in a full screen editor. Changes are only written to the storage when saving
+/// # Arguments
+/// * `storage`: the storage containing the language
+/// * `lang`: the language to edit (created when saving if it doesn't exist yet)
+/// * `term`: the terminal to draw on
+/// # Returns
+/// the names of the saved vocab that were changed together with their new names (`None` if they
+/// were deleted)
+pub fn run(
+    storage: &mut dyn Storage,
+    lang: &str,
+    term: &Terminal,
+) -> Result<Vec<(String, Option<String>)>, VctError> {
+    let working: MemoryStorage = MemoryStorage::copy_of(&*storage, &[lang.to_string()])?;
+    let names: Vec<String> = language_names(lang, &working.header(lang)?);
+    let mut editor: Editor = Editor {
+        lang: lang.to_string(),
+        original: working.load(lang)?,
+        working,
+        filter: String::new(),
+        query: Query::parse("").unwrap(),
+        names,
+        selected: 0,
+        top: 0,
+        mode: Mode::Browse,
+        status: String::new(),
+        quitting: false,
+    };
+    let mut changes: Vec<(String, Option<String>)> = Vec::new();
+    let raw: RawMode = RawMode::enable()?;
+    loop {
+        editor.render(term);
+        let key: Key = read_key();
+        match editor.mode {
+            Mode::Browse => {
+                if editor.browse(key, storage, &mut changes) {
+                    break;
+                }
+            }
+            Mode::Filter => editor.type_filter(key),
+            Mode::Edit(_) => editor.edit(key),
+        }
+    }
+    drop(raw);
+    term.clear_screen().unwrap();
+    term.set_cursor(1, 1).unwrap();
+    Ok(changes)
+}
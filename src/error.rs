@@ -4,6 +4,7 @@ use std::fmt;
 /// enum representing an error that can occur in vct
 #[derive(Debug)]
 #[allow(dead_code)]
+#[allow(clippy::enum_variant_names)]
 pub enum VctErrorKind {
     /// an error that occured while parsing user input or a file
     ParsingError,
@@ -30,6 +31,17 @@ impl VctError {
     }
 }
 
+impl VctError {
+    /// put what was being done when the error occured in front of its message
+    /// # Arguments
+    /// * `msg`: what was being done, e.g. `error importing vocabulary`
+    pub fn context(self, msg: &str) -> VctError {
+        VctError {
+            msg: format!("{}: {}", msg, self.msg),
+        }
+    }
+}
+
 impl Error for VctError {}
 
 impl fmt::Display for VctError {
@@ -0,0 +1,221 @@
+use crate::dict::LineError;
+use crate::question::Direction;
+use serde_derive::Serialize;
+
+/// the prefix of a header line in a dict file
+pub const PREFIX: &str = "#!";
+
+/// the keys a header can contain
+pub const KEYS: [&str; 8] = [
+    "name",
+    "source",
+    "target",
+    "author",
+    "version",
+    "description",
+    "direction",
+    "additionals",
+];
+
+/// metadata describing the vocabulary of a language
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct Header {
+    /// the name shown instead of the name of the language
+    pub name: Option<String>,
+    /// code or name of the language the vocabulary is in
+    pub source: Option<String>,
+    /// code or name of the language of the meanings
+    pub target: Option<String>,
+    pub author: Option<String>,
+    pub version: Option<String>,
+    pub description: Option<String>,
+    /// the direction vocabulary is asked in if none is set
+    pub direction: Option<Direction>,
+    /// the keys the additionals of the vocabulary are expected to have
+    pub additionals: Vec<String>,
+}
+
+/// get the english name of a common language code
+fn language_name(code: &str) -> Option<&'static str> {
+    Some(match code.to_lowercase().as_str() {
+        "ar" => "Arabic",
+        "cs" => "Czech",
+        "da" => "Danish",
+        "de" => "German",
+        "el" => "Greek",
+        "en" => "English",
+        "es" => "Spanish",
+        "fi" => "Finnish",
+        "fr" => "French",
+        "hi" => "Hindi",
+        "it" => "Italian",
+        "ja" => "Japanese",
+        "ko" => "Korean",
+        "la" => "Latin",
+        "nl" => "Dutch",
+        "no" => "Norwegian",
+        "pl" => "Polish",
+        "pt" => "Portuguese",
+        "ru" => "Russian",
+        "sv" => "Swedish",
+        "tr" => "Turkish",
+        "uk" => "Ukrainian",
+        "zh" => "Chinese",
+        _ => return None,
+    })
+}
+
+/// get the name of a direction as it is written in a header
+fn direction_name(direction: Direction) -> &'static str {
+    match direction {
+        Direction::Forward => "forward",
+        Direction::Reverse => "reverse",
+        Direction::Mixed => "mixed",
+    }
+}
+
+impl Header {
+    /// whether no metadata is set
+    pub fn is_empty(&self) -> bool {
+        self == &Header::default()
+    }
+
+    /// set a value of the header
+    /// # Arguments
+    /// * `key`: one of `KEYS`
+    /// * `value`: the new value (an empty value removes it)
+    pub fn set(&mut self, key: &str, value: &str) -> Result<(), String> {
+        let value: &str = value.trim();
+        let text: Option<String> = if value.is_empty() {
+            None
+        } else {
+            Some(value.to_string())
+        };
+        match key {
+            "name" => self.name = text,
+            "source" => self.source = text,
+            "target" => self.target = text,
+            "author" => self.author = text,
+            "version" => self.version = text,
+            "description" => self.description = text,
+            "direction" => {
+                self.direction = match value {
+                    "" => None,
+                    "forward" => Some(Direction::Forward),
+                    "reverse" => Some(Direction::Reverse),
+                    "mixed" => Some(Direction::Mixed),
+                    n => {
+                        return Err(format!(
+                            "'{}' is not a direction. Valid are forward, reverse and mixed",
+                            n
+                        ));
+                    }
+                }
+            }
+            "additionals" => {
+                self.additionals = value
+                    .split(',')
+                    .map(|x| x.trim().to_string())
+                    .filter(|x| !x.is_empty())
+                    .collect()
+            }
+            n => {
+                return Err(format!(
+                    "'{}' is not a header key. Valid are {}",
+                    n,
+                    KEYS.join(", ")
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// get the values that are set
+    /// # Returns
+    /// pairs of a key and its value in the order of `KEYS`
+    pub fn pairs(&self) -> Vec<(&'static str, String)> {
+        let values: [Option<String>; 8] = [
+            self.name.clone(),
+            self.source.clone(),
+            self.target.clone(),
+            self.author.clone(),
+            self.version.clone(),
+            self.description.clone(),
+            self.direction.map(|x| direction_name(x).to_string()),
+            if self.additionals.is_empty() {
+                None
+            } else {
+                Some(self.additionals.join(", "))
+            },
+        ];
+        KEYS.iter()
+            .zip(values)
+            .filter_map(|(key, value)| value.map(|x| (*key, x)))
+            .collect()
+    }
+
+    /// get the title of a language, e.g. `German → English`
+    /// # Arguments
+    /// * `lang`: the name of the language (used if the header has no name or source)
+    pub fn title(&self, lang: &str) -> String {
+        let name = |x: &String| language_name(x).map(|n| n.to_string()).unwrap_or(x.clone());
+        let source: String = match (&self.name, &self.source) {
+            (Some(n), _) => n.clone(),
+            (None, Some(n)) => name(n),
+            (None, None) => lang.to_string(),
+        };
+        match &self.target {
+            Some(n) => format!("{} → {}", source, name(n)),
+            None => source,
+        }
+    }
+
+    /// convert the header to the lines at the top of a dict file
+    pub fn to_lines(&self) -> Vec<String> {
+        self.pairs()
+            .into_iter()
+            .map(|(key, value)| format!("{} {}: {}", PREFIX, key, value))
+            .collect()
+    }
+}
+
+/// check whether a line of a dict file is a header line
+/// # Arguments
+/// * `line`: the line to check
+pub fn is_header(line: &str) -> bool {
+    line.trim_start().starts_with(PREFIX)
+}
+
+/// read the header at the top of a dict file. It ends at the first line that is neither a header
+/// line, a comment nor blank
+/// # Arguments
+/// * `path`: the path of the file (used for the errors)
+/// * `contents`: the contents of the file
+/// # Returns
+/// the header and the errors of invalid header lines
+pub fn parse(path: &str, contents: &str) -> (Header, Vec<LineError>) {
+    let mut out: Header = Header::default();
+    let mut errors: Vec<LineError> = Vec::new();
+    for (idx, line) in contents.lines().enumerate() {
+        let trimmed: &str = line.trim_start();
+        let rest: &str = match trimmed.strip_prefix(PREFIX) {
+            Some(n) => n,
+            None if trimmed.is_empty() || trimmed.starts_with('#') => continue,
+            None => break,
+        };
+        let column: usize = line.chars().count() - trimmed.chars().count() + PREFIX.len() + 1;
+        let result: Result<(), String> = match rest.split_once(':') {
+            Some((key, value)) => out.set(key.trim(), value),
+            None => Err(String::from("expected `key: value`")),
+        };
+        if let Err(reason) = result {
+            errors.push(LineError {
+                path: path.to_string(),
+                line: idx + 1,
+                column,
+                reason,
+            });
+        }
+    }
+    (out, errors)
+}
@@ -0,0 +1,193 @@
+use crate::db;
+use crate::error::*;
+use crate::question::SessionResult;
+use rusqlite::{params, Connection};
+use std::collections::HashMap;
+
+/// accuracy of a group of answers (a language, week or vocab)
+#[derive(Debug, Clone)]
+pub struct Accuracy {
+    /// what the answers were grouped by
+    pub label: String,
+    /// amount of correct answers
+    pub correct: usize,
+    /// amount of almost correct answers
+    pub almost: usize,
+    /// amount of answers
+    pub total: usize,
+}
+
+impl Accuracy {
+    /// get the accuracy in percent. Almost correct answers count half like in the session summary
+    pub fn percent(&self) -> f32 {
+        if self.total == 0 {
+            return 0.0;
+        }
+        ((self.correct as f32 + self.almost as f32 * 0.5) / self.total as f32) * 100.0
+    }
+}
+
+/// struct managing the history of learning sessions in the database
+pub struct History {
+    conn: Connection,
+}
+
+impl History {
+    /// open the session history (the database is created and upgraded if needed)
+    /// # Arguments
+    /// * `path`: path to the database
+    pub fn open(path: &str) -> Result<History, VctError> {
+        let conn: Connection = db::open(path)?;
+        Ok(History { conn })
+    }
+
+    /// write a finished session and all of its answers to the history
+    /// # Arguments
+    /// * `lang`: the language that was learned
+    /// * `started`: unix timestamp of the start of the session
+    /// * `finished`: unix timestamp of the end of the session
+    /// * `session`: the result of the session
+    pub fn record(
+        &mut self,
+        lang: &str,
+        started: i64,
+        finished: i64,
+        session: &SessionResult,
+    ) -> Result<(), VctError> {
+        let tx = match self.conn.transaction() {
+            Ok(n) => n,
+            Err(e) => {
+                return Err(VctError::new(
+                    VctErrorKind::DatabaseError,
+                    format!("error starting transaction: {}", e).as_str(),
+                ));
+            }
+        };
+        if let Err(e) = tx.execute(
+            "INSERT INTO sessions (lang, started, finished) VALUES (?, ?, ?)",
+            params![lang, started, finished],
+        ) {
+            return Err(VctError::new(
+                VctErrorKind::DatabaseError,
+                format!("error writing session: {}", e).as_str(),
+            ));
+        }
+        let id: i64 = tx.last_insert_rowid();
+        for answer in &session.answers {
+            if let Err(e) = tx.execute(
+                "INSERT INTO answers (session, time, lang, vocab, answer, result, kind) VALUES (?, ?, ?, ?, ?, ?, ?)",
+                params![
+                    id,
+                    answer.time,
+                    lang,
+                    answer.vocab,
+                    answer.input,
+                    answer.verdict.as_str(),
+                    answer.kind.as_str()
+                ],
+            ) {
+                return Err(VctError::new(
+                    VctErrorKind::DatabaseError,
+                    format!("error writing answer: {}", e).as_str(),
+                ));
+            }
+        }
+        match tx.commit() {
+            Ok(_) => Ok(()),
+            Err(e) => Err(VctError::new(
+                VctErrorKind::DatabaseError,
+                format!("error committing session: {}", e).as_str(),
+            )),
+        }
+    }
+
+    /// run a query grouping answers and return the accuracy of every group
+    /// # Arguments
+    /// * `group`: the SQL expression to group by
+    /// * `lang`: only include answers of this language (all languages if `None`)
+    /// * `order`: the SQL expression to order the groups by
+    fn accuracy(
+        &self,
+        group: &str,
+        lang: Option<&str>,
+        order: &str,
+    ) -> Result<Vec<Accuracy>, VctError> {
+        let mut sel = match self.conn.prepare(
+            format!(
+                "SELECT {} AS label, SUM(result = 'correct'), SUM(result = 'almost'), COUNT(*) FROM answers WHERE (?1 IS NULL OR lang = ?1) GROUP BY label ORDER BY {}",
+                group, order
+            )
+            .as_str(),
+        ) {
+            Ok(n) => n,
+            Err(e) => {
+                return Err(VctError::new(
+                    VctErrorKind::DatabaseError,
+                    format!("error preparing statistics: {}", e).as_str(),
+                ));
+            }
+        };
+        let rows = sel.query_map(params![lang], |row| {
+            let correct: i64 = row.get(1)?;
+            let almost: i64 = row.get(2)?;
+            let total: i64 = row.get(3)?;
+            Ok(Accuracy {
+                label: row.get(0)?,
+                correct: correct as usize,
+                almost: almost as usize,
+                total: total as usize,
+            })
+        });
+        match rows {
+            Ok(n) => match n.collect::<Result<Vec<Accuracy>, rusqlite::Error>>() {
+                Ok(v) => Ok(v),
+                Err(e) => Err(VctError::new(
+                    VctErrorKind::DatabaseError,
+                    format!("error reading statistics: {}", e).as_str(),
+                )),
+            },
+            Err(e) => Err(VctError::new(
+                VctErrorKind::DatabaseError,
+                format!("error reading statistics: {}", e).as_str(),
+            )),
+        }
+    }
+
+    /// get how often every vocab of a language was answered wrongly (almost correct answers aren't
+    /// counted)
+    /// # Arguments
+    /// * `lang`: the language
+    pub fn missed(&self, lang: &str) -> Result<HashMap<String, usize>, VctError> {
+        let mut out: HashMap<String, usize> = HashMap::new();
+        for entry in self.per_vocab(lang)? {
+            out.insert(
+                entry.label.clone(),
+                entry.total - entry.correct - entry.almost,
+            );
+        }
+        Ok(out)
+    }
+
+    /// get the accuracy of every language
+    pub fn per_language(&self) -> Result<Vec<Accuracy>, VctError> {
+        self.accuracy("lang", None, "label")
+    }
+
+    /// get the accuracy of every week (oldest first)
+    /// # Arguments
+    /// * `lang`: only include answers of this language (all languages if `None`)
+    pub fn per_week(&self, lang: Option<&str>) -> Result<Vec<Accuracy>, VctError> {
+        self.accuracy("strftime('%Y-W%W', time, 'unixepoch')", lang, "label")
+    }
+
+    /// get the accuracy of every vocab of a language (worst first)
+    /// # Arguments
+    /// * `lang`: the language
+    pub fn per_vocab(&self, lang: &str) -> Result<Vec<Accuracy>, VctError> {
+        self.accuracy(
+            "vocab",
+            Some(lang),
+            "(SUM(result = 'correct') + SUM(result = 'almost') * 0.5) / COUNT(*), label",
+        )
+    }
+}
@@ -0,0 +1,133 @@
+use crate::question::Verdict;
+
+/// calculate the damerau-levenshtein distance (optimal string alignment) between two strings.
+/// Insertions, deletions, substitutions and transpositions of adjacent characters count as one
+/// edit each
+/// # Arguments
+/// * `a`: the first string
+/// * `b`: the second string
+/// # Returns
+/// the amount of edits needed to turn `a` into `b`
+pub fn damerau_levenshtein(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut dist: Vec<Vec<usize>> = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for (i, row) in dist.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, cell) in dist[0].iter_mut().enumerate() {
+        *cell = j;
+    }
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost: usize = if a[i - 1] == b[j - 1] { 0 } else { 1 };
+            dist[i][j] = (dist[i - 1][j] + 1)
+                .min(dist[i][j - 1] + 1)
+                .min(dist[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                dist[i][j] = dist[i][j].min(dist[i - 2][j - 2] + 1);
+            }
+        }
+    }
+    dist[a.len()][b.len()]
+}
+
+/// get the amount of edits tolerated for an expected answer. The amount scales with the length of
+/// the answer so short words have to be spelled correctly
+/// # Arguments
+/// * `expected`: the expected answer
+/// * `tolerance`: the tolerated edits per character (`0.0` disables typo tolerance)
+pub fn allowed_distance(expected: &str, tolerance: f32) -> usize {
+    if tolerance <= 0.0 {
+        return 0;
+    }
+    (expected.chars().count() as f32 * tolerance).floor() as usize
+}
+
+/// judge an answer against a list of accepted answers
+/// # Arguments
+/// * `input`: the answer given by the user
+/// * `accepted`: all answers that are accepted
+/// * `tolerance`: the tolerated edits per character
+/// # Returns
+/// `Verdict::Correct` on an exact match, `Verdict::Almost` with the closest accepted answer if it
+/// is within the tolerated distance and `Verdict::Wrong` otherwise
+pub fn judge(input: &str, accepted: &[String], tolerance: f32) -> Verdict {
+    if accepted.iter().any(|x| x == input) {
+        return Verdict::Correct;
+    }
+    let mut best: Option<(usize, &String)> = None;
+    for candidate in accepted {
+        let dist: usize = damerau_levenshtein(input, candidate);
+        if dist > allowed_distance(candidate, tolerance) {
+            continue;
+        }
+        match best {
+            Some((n, _)) if n <= dist => (),
+            _ => best = Some((dist, candidate)),
+        }
+    }
+    match best {
+        Some((_, n)) => Verdict::Almost(n.clone()),
+        None => Verdict::Wrong,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn distance() {
+        assert_eq!(damerau_levenshtein("Hund", "Hund"), 0);
+        assert_eq!(damerau_levenshtein("", ""), 0);
+        assert_eq!(damerau_levenshtein("", "Hund"), 4);
+        assert_eq!(damerau_levenshtein("Hund", ""), 4);
+        assert_eq!(damerau_levenshtein("Hund", "Hudn"), 1);
+        assert_eq!(damerau_levenshtein("Hund", "Hunde"), 1);
+        assert_eq!(damerau_levenshtein("Hund", "Hand"), 1);
+        assert_eq!(damerau_levenshtein("ca", "abc"), 3);
+        // characters count, not bytes
+        assert_eq!(damerau_levenshtein("Müll", "Mull"), 1);
+        assert_eq!(damerau_levenshtein("Straße", "Strasse"), 2);
+        assert_eq!(damerau_levenshtein("日本語", "日語本"), 1);
+    }
+
+    #[test]
+    fn allowed() {
+        assert_eq!(allowed_distance("Hund", 0.0), 0);
+        assert_eq!(allowed_distance("Hund", -1.0), 0);
+        assert_eq!(allowed_distance("Hund", 0.2), 0);
+        assert_eq!(allowed_distance("Hunde", 0.2), 1);
+        assert_eq!(allowed_distance("Schmetterling", 0.2), 2);
+        assert_eq!(allowed_distance("Männer", 0.2), 1);
+        assert_eq!(allowed_distance("", 0.5), 0);
+    }
+
+    #[test]
+    fn verdicts() {
+        let accepted: Vec<String> = vec![String::from("Katze"), String::from("Kater")];
+        assert_eq!(judge("Katze", &accepted, 0.2), Verdict::Correct);
+        assert_eq!(judge("Katze", &accepted, 0.0), Verdict::Correct);
+        assert_eq!(
+            judge("Katez", &accepted, 0.2),
+            Verdict::Almost(String::from("Katze"))
+        );
+        assert_eq!(judge("Katez", &accepted, 0.0), Verdict::Wrong);
+        // the closest accepted answer is suggested
+        assert_eq!(
+            judge("Kate", &accepted, 0.2),
+            Verdict::Almost(String::from("Katze"))
+        );
+        assert_eq!(judge("Kazt", &accepted, 0.2), Verdict::Wrong);
+        assert_eq!(judge("", &accepted, 0.2), Verdict::Wrong);
+        // exactly the tolerated distance is still almost correct
+        let accepted: Vec<String> = vec![String::from("Schmetterling")];
+        assert_eq!(
+            judge("Schmeterlin", &accepted, 0.2),
+            Verdict::Almost(String::from("Schmetterling"))
+        );
+        assert_eq!(judge("Schmetrlin", &accepted, 0.2), Verdict::Wrong);
+        assert_eq!(judge("", &[], 0.2), Verdict::Wrong);
+    }
+}
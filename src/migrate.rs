@@ -0,0 +1,100 @@
+use crate::dict::Vocab;
+use crate::error::*;
+use crate::header::Header;
+use crate::storage::{Entry, Storage};
+
+/// what migrating a language would do
+#[derive(Debug, Clone)]
+pub struct Plan {
+    /// the language to migrate
+    pub lang: String,
+    /// the vocabulary missing on the other side
+    pub vocab: Vec<Vocab>,
+    /// vocabulary already present on the other side (not migrated again)
+    pub present: Vec<Vocab>,
+    /// vocabulary that is in the language more than once (only migrated once)
+    pub repeated: Vec<Vocab>,
+    /// vocabulary with a name present on the other side but different meanings, additionals or
+    /// tags (these are not migrated)
+    pub conflicts: Vec<(Vocab, Vocab)>,
+    /// the metadata of the language (only migrated if the other side has none)
+    pub header: Header,
+}
+
+impl Plan {
+    /// whether the source of the language can be removed without losing vocabulary
+    pub fn complete(&self) -> bool {
+        self.conflicts.is_empty()
+    }
+}
+
+/// load a language of a storage or nothing if it doesn't exist there
+fn load_existing(storage: &dyn Storage, lang: &str) -> Result<Vec<Vocab>, VctError> {
+    if !storage.languages()?.iter().any(|x| x == lang) {
+        return Ok(Vec::new());
+    }
+    Ok(storage
+        .load(lang)?
+        .into_iter()
+        .map(|x: Entry| x.vocab)
+        .collect())
+}
+
+/// plan the migration of a language
+/// # Arguments
+/// * `source`: the storage to migrate from
+/// * `target`: the storage to migrate to
+/// * `lang`: the language to migrate
+pub fn plan(source: &dyn Storage, target: &dyn Storage, lang: &str) -> Result<Plan, VctError> {
+    let target: Vec<Vocab> = load_existing(target, lang)?;
+    let mut out: Plan = Plan {
+        lang: lang.to_string(),
+        vocab: Vec::new(),
+        present: Vec::new(),
+        repeated: Vec::new(),
+        conflicts: Vec::new(),
+        header: source.header(lang)?,
+    };
+    for entry in source.load(lang)? {
+        let voc: Vocab = entry.vocab;
+        if target.contains(&voc) {
+            out.present.push(voc);
+            continue;
+        }
+        if out.vocab.contains(&voc) {
+            out.repeated.push(voc);
+            continue;
+        }
+        match target.iter().find(|x| x.get_name() == voc.get_name()) {
+            Some(n) => out.conflicts.push((voc, n.clone())),
+            None => out.vocab.push(voc),
+        }
+    }
+    Ok(out)
+}
+
+/// write the vocabulary of a plan to the target and read it back
+/// # Arguments
+/// * `target`: the storage to migrate to
+/// * `plan`: the plan to apply
+/// # Returns
+/// the vocabulary of the plan that can't be found in the target after it was written
+pub fn apply(target: &mut dyn Storage, plan: &Plan) -> Result<Vec<Vocab>, VctError> {
+    let lang: &str = plan.lang.as_str();
+    if !plan.header.is_empty() && target.header(lang)?.is_empty() {
+        target.set_header(lang, &plan.header)?;
+    }
+    if plan.vocab.is_empty() {
+        return Ok(Vec::new());
+    }
+    for voc in &plan.vocab {
+        target.insert(lang, voc)?;
+    }
+    let written: Vec<Vocab> = load_existing(&*target, lang)?;
+    Ok(plan
+        .vocab
+        .iter()
+        .filter(|x| !written.contains(x))
+        .cloned()
+        .collect())
+}
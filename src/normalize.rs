@@ -0,0 +1,222 @@
+use crate::cfg::Config;
+use unicode_normalization::char::is_combining_mark;
+use unicode_normalization::UnicodeNormalization;
+
+/// enum representing the unicode normalization form applied to answers
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Form {
+    /// canonical composition (é and e + ◌́ are equal)
+    Nfc,
+    /// compatibility composition (additionally ﬁ and fi are equal)
+    Nfkc,
+    /// no normalization
+    None,
+}
+
+/// struct normalizing answers and meanings before they get compared
+#[derive(Debug, Clone)]
+pub struct Normalizer {
+    pub form: Form,
+    /// whether accents are ignored (`lenient` accents)
+    pub lenient_accents: bool,
+    /// the locale used for case folding (e.g. `tr` for the turkish dotted and dotless i)
+    pub locale: Option<String>,
+    /// whether runs of whitespace are collapsed into a single space
+    pub collapse_whitespace: bool,
+    /// whether punctuation is removed
+    pub strip_punctuation: bool,
+}
+
+impl Normalizer {
+    /// create a normalizer with the default settings (NFC, strict accents, whitespace collapsing)
+    pub fn new() -> Normalizer {
+        Normalizer {
+            form: Form::Nfc,
+            lenient_accents: false,
+            locale: None,
+            collapse_whitespace: true,
+            strip_punctuation: false,
+        }
+    }
+
+    /// create a normalizer from the settings of a language in the config
+    /// # Arguments
+    /// * `conf`: the config
+    /// * `lang`: the language to get the settings of
+    pub fn from_config(conf: &Config, lang: &str) -> Normalizer {
+        let mut out: Normalizer = Normalizer::new();
+        if let Some(n) = conf.lang(lang) {
+            out.form = match n.normalization.as_deref() {
+                Some("nfkc") => Form::Nfkc,
+                Some("none") => Form::None,
+                _ => Form::Nfc,
+            };
+            out.lenient_accents = n.accents.as_deref() == Some("lenient");
+            out.locale = n.locale.clone();
+            out.collapse_whitespace = n.collapse_whitespace.unwrap_or(true);
+            out.strip_punctuation = n.strip_punctuation.unwrap_or(false);
+        }
+        out
+    }
+
+    /// fold the case of a string according to the locale
+    fn fold_case(&self, string: &str) -> String {
+        match self.locale.as_deref() {
+            Some("tr") | Some("az") => string
+                .chars()
+                .map(|x| match x {
+                    'I' => 'ı',
+                    'İ' => 'i',
+                    n => n,
+                })
+                .collect::<String>()
+                .to_lowercase(),
+            _ => string.to_lowercase(),
+        }
+    }
+
+    /// normalize a string
+    /// # Arguments
+    /// * `string`: the string to normalize
+    /// # Returns
+    /// the normalized string
+    pub fn normalize(&self, string: &str) -> String {
+        let mut out: String = match self.form {
+            Form::Nfc => string.nfc().collect(),
+            Form::Nfkc => string.nfkc().collect(),
+            Form::None => string.to_string(),
+        };
+        out = self.fold_case(out.as_str());
+        if self.lenient_accents {
+            out = out.nfd().filter(|x| !is_combining_mark(*x)).nfc().collect();
+        }
+        if self.strip_punctuation {
+            out = out
+                .chars()
+                .filter(|x| x.is_alphanumeric() || x.is_whitespace() || is_combining_mark(*x))
+                .collect();
+        }
+        if self.collapse_whitespace {
+            out = out.split_whitespace().collect::<Vec<&str>>().join(" ");
+        }
+        out.trim().to_string()
+    }
+}
+
+impl Default for Normalizer {
+    fn default() -> Normalizer {
+        Normalizer::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// "é" as a single character and as "e" followed by a combining acute accent
+    const COMPOSED: &str = "caf\u{e9}";
+    const DECOMPOSED: &str = "cafe\u{301}";
+
+    #[test]
+    fn forms() {
+        let mut normalizer: Normalizer = Normalizer::new();
+        assert_eq!(
+            normalizer.normalize(COMPOSED),
+            normalizer.normalize(DECOMPOSED)
+        );
+        assert_ne!(
+            normalizer.normalize("\u{fb01}x"),
+            normalizer.normalize("fix")
+        );
+
+        normalizer.form = Form::Nfkc;
+        assert_eq!(
+            normalizer.normalize(COMPOSED),
+            normalizer.normalize(DECOMPOSED)
+        );
+        assert_eq!(
+            normalizer.normalize("\u{fb01}x"),
+            normalizer.normalize("fix")
+        );
+
+        normalizer.form = Form::None;
+        assert_ne!(
+            normalizer.normalize(COMPOSED),
+            normalizer.normalize(DECOMPOSED)
+        );
+    }
+
+    #[test]
+    fn case_folding() {
+        let mut normalizer: Normalizer = Normalizer::new();
+        assert_eq!(normalizer.normalize("Hund"), "hund");
+        assert_eq!(normalizer.normalize("ISPARTA"), "isparta");
+        // without a locale İ keeps its dot as a combining mark
+        assert_eq!(normalizer.normalize("İstanbul"), "i\u{307}stanbul");
+
+        normalizer.locale = Some(String::from("tr"));
+        assert_eq!(normalizer.normalize("İstanbul"), "istanbul");
+        assert_eq!(normalizer.normalize("ISPARTA"), "ısparta");
+        assert_eq!(normalizer.normalize("ılık"), "ılık");
+        normalizer.locale = Some(String::from("az"));
+        assert_eq!(normalizer.normalize("İI"), "iı");
+    }
+
+    #[test]
+    fn accents() {
+        let mut normalizer: Normalizer = Normalizer::new();
+        assert_ne!(normalizer.normalize(COMPOSED), "cafe");
+        assert_eq!(normalizer.normalize("Ärger"), "ärger");
+
+        normalizer.lenient_accents = true;
+        assert_eq!(normalizer.normalize(COMPOSED), "cafe");
+        assert_eq!(normalizer.normalize(DECOMPOSED), "cafe");
+        assert_eq!(normalizer.normalize("Ärger"), "arger");
+        assert_eq!(normalizer.normalize("İstanbul"), "istanbul");
+    }
+
+    #[test]
+    fn whitespace_and_punctuation() {
+        let mut normalizer: Normalizer = Normalizer::new();
+        assert_eq!(normalizer.normalize("  to\t put   on "), "to put on");
+        assert_eq!(normalizer.normalize("Guten Tag!"), "guten tag!");
+
+        normalizer.collapse_whitespace = false;
+        assert_eq!(normalizer.normalize("  to\t put   on "), "to\t put   on");
+
+        normalizer.collapse_whitespace = true;
+        normalizer.strip_punctuation = true;
+        assert_eq!(normalizer.normalize("Guten Tag!"), "guten tag");
+        assert_eq!(normalizer.normalize("l'eau - (f)"), "leau f");
+        // combining accents aren't punctuation
+        normalizer.form = Form::None;
+        assert_eq!(normalizer.normalize(DECOMPOSED), DECOMPOSED);
+    }
+
+    #[test]
+    fn config() {
+        let conf: Config = toml::from_str(
+            "[languages.tr]\n\
+             normalization = \"nfkc\"\n\
+             accents = \"lenient\"\n\
+             locale = \"tr\"\n\
+             collapse_whitespace = false\n\
+             strip_punctuation = true\n",
+        )
+        .unwrap();
+        let normalizer: Normalizer = Normalizer::from_config(&conf, "tr");
+        assert_eq!(normalizer.form, Form::Nfkc);
+        assert!(normalizer.lenient_accents);
+        assert_eq!(normalizer.locale.as_deref(), Some("tr"));
+        assert!(!normalizer.collapse_whitespace);
+        assert!(normalizer.strip_punctuation);
+
+        // languages without settings use the defaults
+        let normalizer: Normalizer = Normalizer::from_config(&conf, "de");
+        assert_eq!(normalizer.form, Form::Nfc);
+        assert!(!normalizer.lenient_accents);
+        assert_eq!(normalizer.locale, None);
+        assert!(normalizer.collapse_whitespace);
+        assert!(!normalizer.strip_punctuation);
+    }
+}
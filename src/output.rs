@@ -0,0 +1,194 @@
+use crate::csvfile::{default_columns, record, Column};
+use crate::dict::Vocab;
+use crate::error::*;
+use crate::header::Header;
+use crate::query::LanguageHits;
+use crate::question::SessionReport;
+use serde_derive::Serialize;
+
+/// enum representing how results are printed
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    /// colored text for humans
+    Pretty,
+    Json,
+    /// tab seperated values with a header row
+    Tsv,
+}
+
+impl Format {
+    /// get the output format from the value of `--format`
+    /// # Arguments
+    /// * `name`: the value of `--format` (`None` for pretty output)
+    pub fn from_name(name: Option<&str>) -> Result<Format, VctError> {
+        match name {
+            None => Ok(Format::Pretty),
+            Some("json") => Ok(Format::Json),
+            Some("tsv") => Ok(Format::Tsv),
+            Some(n) => Err(VctError::new(
+                VctErrorKind::ParamError,
+                format!(
+                    "'{}' can't be used for output. Valid are 'json' and 'tsv'",
+                    n
+                )
+                .as_str(),
+            )),
+        }
+    }
+}
+
+/// a vocab found by a query as it is serialized
+#[derive(Serialize)]
+struct QueryHit<'a> {
+    language: &'a str,
+    title: String,
+    source: &'a str,
+    id: i64,
+    vocab: &'a Vocab,
+}
+
+/// the vocabulary of a language as it is serialized
+#[derive(Serialize)]
+struct Language<'a> {
+    language: &'a str,
+    header: &'a Header,
+    vocab: &'a [Vocab],
+}
+
+/// write rows as tab seperated values
+/// # Arguments
+/// * `header`: the header row
+/// * `rows`: the rows below it
+fn tsv(header: Vec<String>, rows: Vec<Vec<String>>) -> Result<String, VctError> {
+    let tsv_error = |e: &dyn std::fmt::Display| {
+        VctError::new(
+            VctErrorKind::ParsingError,
+            format!("error writing tsv: {}", e).as_str(),
+        )
+    };
+    let mut writer = csv::WriterBuilder::new()
+        .delimiter(b'\t')
+        .from_writer(Vec::new());
+    for row in std::iter::once(header).chain(rows) {
+        writer.write_record(row).map_err(|e| tsv_error(&e))?;
+    }
+    let bytes: Vec<u8> = writer.into_inner().map_err(|e| tsv_error(&e))?;
+    String::from_utf8(bytes).map_err(|e| tsv_error(&e))
+}
+
+/// serialize a value to JSON
+fn json<T: serde::Serialize>(value: &T) -> Result<String, VctError> {
+    serde_json::to_string_pretty(value)
+        .map(|x| x + "\n")
+        .map_err(|e| {
+            VctError::new(
+                VctErrorKind::ParsingError,
+                format!("error writing json: {}", e).as_str(),
+            )
+        })
+}
+
+/// format the vocabulary found by a query
+/// # Arguments
+/// * `results`: the vocabulary found grouped by language
+/// * `format`: `Format::Json` or `Format::Tsv`
+pub fn query_results(results: &[LanguageHits], format: Format) -> Result<String, VctError> {
+    if format == Format::Json {
+        let hits: Vec<QueryHit> = results
+            .iter()
+            .flat_map(|lang| {
+                lang.hits.iter().map(|hit| QueryHit {
+                    language: lang.lang.as_str(),
+                    title: lang.header.title(lang.lang.as_str()),
+                    source: hit.source.as_str(),
+                    id: hit.entry.id,
+                    vocab: &hit.entry.vocab,
+                })
+            })
+            .collect();
+        return json(&hits);
+    }
+    let vocab: Vec<Vocab> = results
+        .iter()
+        .flat_map(|x| x.hits.iter().map(|n| n.entry.vocab.clone()))
+        .collect();
+    let columns: Vec<Column> = default_columns(&vocab);
+    let header: Vec<String> = ["language", "source"]
+        .iter()
+        .map(|x| x.to_string())
+        .chain(columns.iter().map(|x| x.name()))
+        .collect();
+    let rows: Vec<Vec<String>> = results
+        .iter()
+        .flat_map(|lang| {
+            lang.hits.iter().map(|hit| {
+                let mut row: Vec<String> = vec![lang.lang.clone(), hit.source.clone()];
+                row.extend(record(&hit.entry.vocab, &columns));
+                row
+            })
+        })
+        .collect();
+    tsv(header, rows)
+}
+
+/// format the vocabulary of a language
+/// # Arguments
+/// * `lang`: the language
+/// * `header`: its header
+/// * `vocab`: its vocabulary
+/// * `format`: `Format::Json` or `Format::Tsv` (which leaves out the header)
+pub fn vocabulary(
+    lang: &str,
+    header: &Header,
+    vocab: &[Vocab],
+    format: Format,
+) -> Result<String, VctError> {
+    if format == Format::Json {
+        return json(&Language {
+            language: lang,
+            header,
+            vocab,
+        });
+    }
+    let columns: Vec<Column> = default_columns(vocab);
+    tsv(
+        columns.iter().map(|x| x.name()).collect(),
+        vocab.iter().map(|x| record(x, &columns)).collect(),
+    )
+}
+
+/// format the summary of a learning session
+/// # Arguments
+/// * `report`: the summary
+/// * `format`: `Format::Json` or `Format::Tsv` (which only contains the totals)
+pub fn session(report: &SessionReport, format: Format) -> Result<String, VctError> {
+    if format == Format::Json {
+        return json(report);
+    }
+    let mut header: Vec<&str> = vec!["language", "total", "correct", "almost", "retries", "score"];
+    let mut row: Vec<String> = vec![
+        report.language.clone(),
+        report.total.to_string(),
+        report.correct.to_string(),
+        report.almost.to_string(),
+        report.retries.to_string(),
+        format!("{:.1}", report.score),
+    ];
+    if let Some(n) = &report.additionals {
+        header.extend([
+            "additionals_total",
+            "additionals_correct",
+            "additionals_almost",
+            "additionals_score",
+        ]);
+        row.extend([
+            n.total.to_string(),
+            n.correct.to_string(),
+            n.almost.to_string(),
+            n.score
+                .map(|x| format!("{:.1}", x))
+                .unwrap_or(String::from("-")),
+        ]);
+    }
+    tsv(header.iter().map(|x| x.to_string()).collect(), vec![row])
+}
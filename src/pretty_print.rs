@@ -1,4 +1,6 @@
-use crate::Vocab;
+use crate::dict::Vocab;
+use crate::header::Header;
+use crate::query::{LanguageHits, Part, Query};
 use btui::effects::{Color, Special};
 use btui::print::{fg, sp};
 
@@ -16,14 +18,7 @@ pub fn pretty_print(vocab: Vec<Vocab>) -> String {
             let adds: String = n
                 .iter()
                 .map(|x| {
-                    let mut parts = x.split(':');
-                    let key: &str = match parts.next() {
-                        Some(n) => n,
-                        None => {
-                            return String::new();
-                        }
-                    };
-                    let value: &str = match parts.next() {
+                    let (key, value): (&str, &str) = match x.split_once(':') {
                         Some(n) => n,
                         None => {
                             return String::new();
@@ -40,7 +35,174 @@ pub fn pretty_print(vocab: Vec<Vocab>) -> String {
                 .collect();
             out.push_str(adds.as_str());
         }
+        if !voc.get_tags().is_empty() {
+            out.push_str(
+                format!(
+                    "  {}tags: {}{}\n",
+                    fg(Color::Magenta),
+                    voc.get_tags().join(", "),
+                    sp(Special::Reset)
+                )
+                .as_str(),
+            );
+        }
     }
 
     out
 }
+
+/// pretty print the metadata of a language
+/// # Arguments
+/// * `lang`: the name of the language
+/// * `header`: the header of the language
+pub fn pretty_header(lang: &str, header: &Header) -> String {
+    let mut out: String = format!(
+        "{}{}{}\n",
+        sp(Special::Bold),
+        header.title(lang),
+        sp(Special::Reset)
+    );
+    if let Some(n) = &header.description {
+        out.push_str(format!("{}\n", n).as_str());
+    }
+    let by: Vec<String> = [
+        header.author.as_ref().map(|x| format!("by {}", x)),
+        header.version.as_ref().map(|x| format!("version {}", x)),
+    ]
+    .into_iter()
+    .flatten()
+    .collect();
+    if !by.is_empty() {
+        out.push_str(
+            format!(
+                "{}{}{}\n",
+                fg(Color::White),
+                by.join(", "),
+                sp(Special::Reset)
+            )
+            .as_str(),
+        );
+    }
+    if !header.additionals.is_empty() {
+        out.push_str(
+            format!(
+                "{}additionals: {}{}\n",
+                fg(Color::Yellow),
+                header.additionals.join(", "),
+                sp(Special::Reset)
+            )
+            .as_str(),
+        );
+    }
+    out
+}
+
+/// surround the spans of a text with an effect
+/// # Arguments
+/// * `text`: the text
+/// * `spans`: the start and end in bytes of the parts to highlight
+/// * `restore`: the effect of the rest of the text
+fn highlight(text: &str, spans: &[(usize, usize)], restore: &str) -> String {
+    let mut out: String = String::new();
+    let mut last: usize = 0;
+    for (start, end) in spans {
+        out.push_str(&text[last..*start]);
+        out.push_str(
+            format!(
+                "{}{}{}{}{}",
+                sp(Special::Bold),
+                sp(Special::Underline),
+                &text[*start..*end],
+                sp(Special::Reset),
+                restore
+            )
+            .as_str(),
+        );
+        last = *end;
+    }
+    out.push_str(&text[last..]);
+    out
+}
+
+/// print the vocabulary found by a query grouped by language
+/// # Arguments
+/// * `results`: the vocabulary found
+/// * `query`: the query (what it matched is highlighted)
+/// * `color`: whether colors and highlighting are used (e.g. only if stdout is a terminal)
+pub fn pretty_results(results: &[LanguageHits], query: &Query, color: bool) -> String {
+    // plain text mustn't contain any escape codes
+    let paint = |text_color: Color, part: Part, text: &str| -> String {
+        if !color {
+            return text.to_string();
+        }
+        let effect: String = fg(text_color).to_string();
+        format!(
+            "{}{}{}",
+            effect,
+            highlight(text, &query.spans(part, text), effect.as_str()),
+            sp(Special::Reset)
+        )
+    };
+    let mut out: String = String::new();
+    for lang in results {
+        let title: String = lang.header.title(lang.lang.as_str());
+        let title: String = if title == lang.lang {
+            title
+        } else {
+            format!("{} ({})", title, lang.lang)
+        };
+        if color {
+            out.push_str(
+                format!("\n{}{}{}\n", sp(Special::Bold), title, sp(Special::Reset)).as_str(),
+            );
+        } else {
+            out.push_str(format!("\n{}\n", title).as_str());
+        }
+        for hit in &lang.hits {
+            let voc: &Vocab = &hit.entry.vocab;
+            let source: String = if color {
+                format!("{}{}{}", fg(Color::White), hit.source, sp(Special::Reset))
+            } else {
+                hit.source.clone()
+            };
+            out.push_str(
+                format!(
+                    "  {}: {}\n",
+                    paint(Color::Green, Part::Name, voc.get_name().as_str()),
+                    source
+                )
+                .as_str(),
+            );
+            for meaning in voc.get_meanings() {
+                out.push_str(
+                    format!(
+                        "    - {}\n",
+                        paint(Color::Blue, Part::Meaning, meaning.as_str())
+                    )
+                    .as_str(),
+                );
+            }
+            for add in voc.get_additionals().unwrap_or_default() {
+                if let Some((key, value)) = add.split_once(':') {
+                    out.push_str(
+                        format!(
+                            "    {}: {}\n",
+                            key,
+                            paint(Color::Yellow, Part::Additional(key), value)
+                        )
+                        .as_str(),
+                    );
+                }
+            }
+            if !voc.get_tags().is_empty() {
+                let tags: Vec<String> = voc
+                    .get_tags()
+                    .iter()
+                    .map(|x| paint(Color::Magenta, Part::Tag, x))
+                    .collect();
+                out.push_str(format!("    tags: {}\n", tags.join(", ")).as_str());
+            }
+        }
+    }
+    out
+}
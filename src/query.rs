@@ -1,24 +1,21 @@
-use crate::cfg::*;
 use crate::dict::Vocab;
 use crate::error::*;
-use rusqlite::{Connection, Result};
-use std::fs::read_to_string;
+use crate::header::Header;
+use crate::storage::{Entry, Storage};
+use regex::Regex;
 
 /// walk a directory recursively and return all the files found
 pub fn walk_through_dir(path: String) -> Vec<String> {
     let mut out: Vec<String> = Vec::new();
     if let Ok(n) = std::fs::read_dir(path) {
-        for result in n {
-            if let Ok(entry) = result {
-                if let Ok(ftype) = entry.file_type() {
-                    if ftype.is_file() {
-                        out.push(entry.path().into_os_string().into_string().unwrap());
-                    } else if ftype.is_dir() {
-                        for i in
-                            walk_through_dir(entry.path().into_os_string().into_string().unwrap())
-                        {
-                            out.push(i);
-                        }
+        for entry in n.flatten() {
+            if let Ok(ftype) = entry.file_type() {
+                if ftype.is_file() {
+                    out.push(entry.path().into_os_string().into_string().unwrap());
+                } else if ftype.is_dir() {
+                    for i in walk_through_dir(entry.path().into_os_string().into_string().unwrap())
+                    {
+                        out.push(i);
                     }
                 }
             }
@@ -27,110 +24,569 @@ pub fn walk_through_dir(path: String) -> Vec<String> {
     out
 }
 
-pub fn query(
-    query_string: String,
-    config_dir: String,
-    conf: &Config,
-    usedb: bool,
-) -> Result<Vec<Vocab>, VctError> {
-    let mut found: Vec<Vocab> = Vec::new();
-    if usedb {
-        let mut path: String = match &conf.dbpath {
-            Some(n) => n.to_string(),
-            None => String::from("db.sqlite"),
-        };
-        if !path.starts_with('/') {
-            path = format!("{}/{}", config_dir, path);
-        }
-        let db = match Connection::open(path.as_str()) {
-            Ok(n) => n,
-            Err(_) => {
-                return Err(VctError::new(
-                    VctErrorKind::DatabaseError,
-                    "error connecting to database",
-                ));
+/// the part of a vocab a term is matched against
+#[derive(Debug, Clone, PartialEq)]
+enum Field {
+    /// the name, meanings, values of the additionals and tags
+    Any,
+    Name,
+    Meaning,
+    Tag,
+    /// the name of the language (or the name in its header)
+    Lang,
+    /// the value of the additionals with a key
+    Additional(String),
+}
+
+/// a part of a vocab that is shown (e.g. to highlight what a query matched)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Part<'a> {
+    Name,
+    Meaning,
+    Tag,
+    /// the value of the additional with a key
+    Additional(&'a str),
+}
+
+impl Field {
+    /// check whether a term of this field is matched against a part of a vocab
+    fn covers(&self, part: Part) -> bool {
+        match (self, part) {
+            (Field::Any, _)
+            | (Field::Name, Part::Name)
+            | (Field::Meaning, Part::Meaning)
+            | (Field::Tag, Part::Tag) => true,
+            (Field::Additional(key), Part::Additional(n)) => key.to_lowercase() == n.to_lowercase(),
+            _ => false,
+        }
+    }
+
+    /// get the field of a prefix like `name:`
+    fn from_prefix(prefix: &str) -> Option<Field> {
+        match prefix {
+            "name" => Some(Field::Name),
+            "meaning" | "meanings" => Some(Field::Meaning),
+            "tag" | "tags" => Some(Field::Tag),
+            "lang" | "language" => Some(Field::Lang),
+            _ => None,
+        }
+    }
+}
+
+/// how the text of a field is matched
+#[derive(Debug, Clone)]
+enum Pattern {
+    /// a regular expression (case insensitive text and whole words are regular expressions as
+    /// well)
+    Regex(Regex),
+    /// anything (used for `key=` to find vocabulary having an additional)
+    Any,
+}
+
+impl Pattern {
+    /// check whether a text matches
+    fn is_match(&self, text: &str) -> bool {
+        match self {
+            Pattern::Regex(n) => n.is_match(text),
+            Pattern::Any => true,
+        }
+    }
+
+    /// find all matches in a text
+    /// # Returns
+    /// the start and end of every match in bytes
+    fn find_all(&self, text: &str) -> Vec<(usize, usize)> {
+        match self {
+            Pattern::Regex(n) => n
+                .find_iter(text)
+                .filter(|x| !x.is_empty())
+                .map(|x| (x.start(), x.end()))
+                .collect(),
+            Pattern::Any => Vec::new(),
+        }
+    }
+}
+
+/// a field together with the pattern it has to match
+#[derive(Debug, Clone)]
+struct Term {
+    field: Field,
+    pattern: Pattern,
+}
+
+/// a parsed query
+#[derive(Debug, Clone)]
+enum Expr {
+    Term(Term),
+    Not(Box<Expr>),
+    And(Vec<Expr>),
+    Or(Vec<Expr>),
+}
+
+/// a query for vocabulary, e.g. `name:hund OR (tag:animal AND NOT gender=m)`
+#[derive(Debug, Clone)]
+pub struct Query {
+    expr: Expr,
+}
+
+/// error returned for an invalid query
+fn query_error(msg: String) -> VctError {
+    VctError::new(
+        VctErrorKind::ParamError,
+        format!("invalid query: {}", msg).as_str(),
+    )
+}
+
+/// split a query into words and parentheses. Quoted text and regular expressions between slashes
+/// stay in one word
+fn tokenize(query: &str) -> Result<Vec<String>, VctError> {
+    let mut out: Vec<String> = Vec::new();
+    let mut cur: String = String::new();
+    let mut chars = query.chars().peekable();
+    while let Some(c) = chars.next() {
+        let value_start: bool = cur.is_empty() || cur.ends_with(':') || cur.ends_with('=');
+        match c {
+            '"' | '/' if c == '"' || value_start => {
+                cur.push(c);
+                loop {
+                    match chars.next() {
+                        Some('\\') if c == '/' => {
+                            cur.push('\\');
+                            if let Some(n) = chars.next() {
+                                cur.push(n);
+                            }
+                        }
+                        Some(n) => {
+                            cur.push(n);
+                            if n == c {
+                                break;
+                            }
+                        }
+                        None => {
+                            return Err(query_error(format!("missing closing {}", c)));
+                        }
+                    }
+                }
+            }
+            '(' | ')' => {
+                if !cur.is_empty() {
+                    out.push(std::mem::take(&mut cur));
+                }
+                out.push(c.to_string());
+            }
+            c if c.is_whitespace() => {
+                if !cur.is_empty() {
+                    out.push(std::mem::take(&mut cur));
+                }
             }
+            c => cur.push(c),
+        }
+    }
+    if !cur.is_empty() {
+        out.push(cur);
+    }
+    Ok(out)
+}
+
+/// parse the value of a term
+fn parse_pattern(value: &str, field: &Field) -> Result<Pattern, VctError> {
+    let regex = |pattern: &str| -> Result<Pattern, VctError> {
+        match Regex::new(pattern) {
+            Ok(n) => Ok(Pattern::Regex(n)),
+            Err(e) => Err(query_error(format!("invalid regex '{}': {}", pattern, e))),
+        }
+    };
+    if value.len() > 1 && value.starts_with('/') && value.ends_with('/') {
+        return regex(&value[1..value.len() - 1]);
+    }
+    if value.len() > 1 && value.starts_with('<') && value.ends_with('>') {
+        return regex(format!(r"(?i)\b{}\b", regex::escape(&value[1..value.len() - 1])).as_str());
+    }
+    let text: &str = if value.len() > 1 && value.starts_with('"') && value.ends_with('"') {
+        &value[1..value.len() - 1]
+    } else {
+        value
+    };
+    if text.is_empty() {
+        return match field {
+            Field::Additional(_) => Ok(Pattern::Any),
+            _ => Err(query_error(String::from("empty search term"))),
         };
-        match db.execute("CREATE TABLE IF NOT EXISTS vocab (lang VARCHAR(256) NOT NULL, name VARCHAR(256) NOT NULL, meanings VARCHAR(256) NOT NULL, additionals VARCHAR(256))", []) {
-            Ok(_) => (),
-            Err(_) => {
-                return Err(VctError::new(VctErrorKind::DatabaseError, "error while creating database"));
+    }
+    regex(format!("(?i){}", regex::escape(text)).as_str())
+}
+
+/// parse a word of a query that isn't an operator
+fn parse_term(word: &str) -> Result<Term, VctError> {
+    // the field ends at the first `:` or `=` that isn't part of the value
+    let end: usize = word.find(['"', '/', '<']).unwrap_or(word.len());
+    let (field, value): (Field, &str) = match word[..end].find([':', '=']) {
+        Some(n) if word[n..].starts_with(':') => {
+            let prefix: &str = &word[..n];
+            match Field::from_prefix(prefix.to_lowercase().as_str()) {
+                Some(f) => (f, &word[n + 1..]),
+                None if !prefix.is_empty() && prefix.chars().all(|x| x.is_alphabetic()) => {
+                    return Err(query_error(format!(
+                        "unknown field '{}'. Valid are name, meaning, tag and lang (use key=value for additionals)",
+                        prefix
+                    )));
+                }
+                None => (Field::Any, word),
             }
         }
-        let mut sel = match db.prepare("SELECT name, meanings, additionals FROM vocab") {
-            Ok(n) => n,
-            Err(_) => {
-                return Err(VctError::new(VctErrorKind::DatabaseError, "problem with the language provided and the database. Maybe your vocab is in a dict file? Try `--nodb` to disable the database"));
+        Some(n) if n > 0 => (Field::Additional(word[..n].to_string()), &word[n + 1..]),
+        _ => (Field::Any, word),
+    };
+    Ok(Term {
+        pattern: parse_pattern(value, &field)?,
+        field,
+    })
+}
+
+/// parser turning the words of a query into an expression
+struct Parser {
+    words: Vec<String>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&str> {
+        self.words.get(self.pos).map(|x| x.as_str())
+    }
+
+    /// parse terms combined with `OR`
+    fn or(&mut self) -> Result<Expr, VctError> {
+        let mut out: Vec<Expr> = vec![self.and()?];
+        while self.peek() == Some("OR") {
+            self.pos += 1;
+            out.push(self.and()?);
+        }
+        Ok(if out.len() == 1 {
+            out.remove(0)
+        } else {
+            Expr::Or(out)
+        })
+    }
+
+    /// parse terms combined with `AND` (or just written after each other)
+    fn and(&mut self) -> Result<Expr, VctError> {
+        let mut out: Vec<Expr> = vec![self.unary()?];
+        loop {
+            match self.peek() {
+                Some("AND") => self.pos += 1,
+                Some("OR") | Some(")") | None => break,
+                _ => {}
+            }
+            out.push(self.unary()?);
+        }
+        Ok(if out.len() == 1 {
+            out.remove(0)
+        } else {
+            Expr::And(out)
+        })
+    }
+
+    /// parse a term, a negated term or an expression in parentheses
+    fn unary(&mut self) -> Result<Expr, VctError> {
+        let word: String = match self.peek() {
+            Some(n) => n.to_string(),
+            None => {
+                return Err(query_error(String::from(
+                    "expected a search term at the end",
+                )))
             }
         };
-        let vocab_iter = sel.query_map([], |row| {
-            let out: Vocab;
-            let name: Result<String, rusqlite::Error> = row.get(0);
-            let meanings: Result<String, rusqlite::Error> = row.get(1);
-            let additionals: Result<Option<String>, rusqlite::Error> = row.get(2);
-            if name.as_ref().unwrap().as_str().contains(&query_string)
-                || meanings.as_ref().unwrap().as_str().contains(&query_string)
-            {
-                if let Some(adds) = additionals.unwrap() {
-                    out = match Vocab::from_string(format!(
-                        "{};{};{}",
-                        name.unwrap(),
-                        meanings.unwrap(),
-                        adds
-                    )) {
-                        Ok(n) => n,
-                        Err(_) => {
-                            return Err(rusqlite::Error::ExecuteReturnedResults);
-                        }
-                    };
-                } else {
-                    out = match Vocab::from_string(format!(
-                        "{};{}",
-                        name.unwrap(),
-                        meanings.unwrap()
-                    )) {
-                        Ok(n) => n,
-                        Err(_) => {
-                            return Err(rusqlite::Error::ExecuteReturnedResults);
-                        }
-                    };
+        self.pos += 1;
+        match word.as_str() {
+            "NOT" => Ok(Expr::Not(Box::new(self.unary()?))),
+            "(" => {
+                let out: Expr = self.or()?;
+                if self.peek() != Some(")") {
+                    return Err(query_error(String::from("missing closing )")));
                 }
-                return Ok(out);
-            } else {
-                return Err(rusqlite::Error::ExecuteReturnedResults);
-            }
-        });
-        let vocab: Vec<Vocab> = vocab_iter
-            .unwrap()
-            .filter(|x| !x.is_err())
-            .map(|x| x.unwrap())
-            .collect();
-        for i in vocab {
-            found.push(i);
-        }
-    }
-    let dir: String = config_dir;
-    let paths: Vec<String> = walk_through_dir(format!(
-        "{}/{}",
-        dir,
-        conf.dict.as_ref().unwrap_or(&String::from("dicts"))
-    ));
-    for path in paths {
-        let contents: String = match read_to_string(path.as_str()) {
-            Ok(n) => n,
-            Err(_) => {
-                continue;
+                self.pos += 1;
+                Ok(out)
             }
+            "AND" | "OR" | ")" => Err(query_error(format!("unexpected {}", word))),
+            n => Ok(Expr::Term(parse_term(n)?)),
+        }
+    }
+}
+
+impl Query {
+    /// parse a query. Words are combined with `AND` (the default), `OR` and `NOT` and can be
+    /// grouped with parentheses. A word is matched against every field unless it starts with
+    /// `name:`, `meaning:`, `tag:` or `lang:` or is an additional like `gender=m`. Values are
+    /// case insensitive substrings, `"quoted text"`, whole words in `<angle brackets>` or regular
+    /// expressions in `/slashes/`
+    /// # Arguments
+    /// * `query`: the query to parse
+    pub fn parse(query: &str) -> Result<Query, VctError> {
+        let mut parser: Parser = Parser {
+            words: tokenize(query)?,
+            pos: 0,
         };
-        for line in contents
-            .as_str()
-            .lines()
-            .filter(|line| line.contains(&query_string))
-        {
-            if let Ok(n) = Vocab::from_string(line.chars().filter(|x| x != &'\n').collect()) {
-                found.push(n);
+        if parser.words.is_empty() {
+            return Ok(Query {
+                expr: Expr::And(Vec::new()),
+            });
+        }
+        let expr: Expr = parser.or()?;
+        if let Some(n) = parser.peek() {
+            return Err(query_error(format!("unexpected {}", n)));
+        }
+        Ok(Query { expr })
+    }
+
+    /// check whether a vocab matches the query
+    /// # Arguments
+    /// * `langs`: the names of the language of the vocab (e.g. its name and the name in its header)
+    /// * `vocab`: the vocab to check
+    pub fn matches(&self, langs: &[String], vocab: &Vocab) -> bool {
+        eval(&self.expr, langs, vocab)
+    }
+
+    /// find what the query matches in a part of a vocab. Negated terms aren't highlighted
+    /// # Arguments
+    /// * `part`: the part of the vocab the text belongs to
+    /// * `text`: the text of the part
+    /// # Returns
+    /// the start and end in bytes of the matched substrings, sorted and without overlaps
+    pub fn spans(&self, part: Part, text: &str) -> Vec<(usize, usize)> {
+        let mut found: Vec<(usize, usize)> = Vec::new();
+        collect_spans(&self.expr, part, text, &mut found);
+        found.sort_unstable();
+        let mut out: Vec<(usize, usize)> = Vec::new();
+        for (start, end) in found {
+            match out.last_mut() {
+                Some(last) if start <= last.1 => last.1 = last.1.max(end),
+                _ => out.push((start, end)),
+            }
+        }
+        out
+    }
+}
+
+fn collect_spans(expr: &Expr, part: Part, text: &str, out: &mut Vec<(usize, usize)>) {
+    match expr {
+        Expr::Term(term) if term.field.covers(part) => out.append(&mut term.pattern.find_all(text)),
+        Expr::Term(_) | Expr::Not(_) => {}
+        Expr::And(n) | Expr::Or(n) => {
+            for x in n {
+                collect_spans(x, part, text, out);
             }
         }
     }
-    Ok(found)
+}
+
+/// get the texts of a vocab a term is matched against
+fn field_texts(field: &Field, langs: &[String], vocab: &Vocab) -> Vec<String> {
+    let additionals: Vec<(String, String)> = vocab
+        .get_additionals()
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|x| x.split_once(':'))
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect();
+    match field {
+        Field::Any => {
+            let mut out: Vec<String> = vec![vocab.get_name()];
+            out.append(&mut vocab.get_meanings());
+            out.extend(additionals.into_iter().map(|x| x.1));
+            out.append(&mut vocab.get_tags());
+            out
+        }
+        Field::Name => vec![vocab.get_name()],
+        Field::Meaning => vocab.get_meanings(),
+        Field::Tag => vocab.get_tags(),
+        Field::Lang => langs.to_vec(),
+        Field::Additional(key) => additionals
+            .into_iter()
+            .filter(|x| x.0.to_lowercase() == key.to_lowercase())
+            .map(|x| x.1)
+            .collect(),
+    }
+}
+
+fn eval(expr: &Expr, langs: &[String], vocab: &Vocab) -> bool {
+    match expr {
+        Expr::Term(term) => field_texts(&term.field, langs, vocab)
+            .iter()
+            .any(|x| term.pattern.is_match(x)),
+        Expr::Not(n) => !eval(n, langs, vocab),
+        Expr::And(n) => n.iter().all(|x| eval(x, langs, vocab)),
+        Expr::Or(n) => n.iter().any(|x| eval(x, langs, vocab)),
+    }
+}
+
+/// get the names a language can be found with in a query
+/// # Arguments
+/// * `lang`: the language
+/// * `header`: the header of the language
+pub fn language_names(lang: &str, header: &Header) -> Vec<String> {
+    let mut out: Vec<String> = vec![lang.to_string()];
+    out.extend(header.name.iter().cloned());
+    out.extend(header.source.iter().cloned());
+    out
+}
+
+/// a vocab found by a query
+#[derive(Debug, Clone)]
+pub struct Hit {
+    /// where the vocab is stored (e.g. the dict file and line)
+    pub source: String,
+    pub entry: Entry,
+}
+
+/// the vocabulary of a language found by a query
+#[derive(Debug, Clone)]
+pub struct LanguageHits {
+    pub lang: String,
+    pub header: Header,
+    pub hits: Vec<Hit>,
+}
+
+/// search every language of a storage for vocabulary matching a query
+/// # Arguments
+/// * `query`: the query
+/// * `storage`: the storage to search
+/// # Returns
+/// the vocabulary found grouped by language
+pub fn query(query: &Query, storage: &dyn Storage) -> Result<Vec<LanguageHits>, VctError> {
+    let mut out: Vec<LanguageHits> = Vec::new();
+    for (lang, entry) in storage.search(query)? {
+        let hit: Hit = Hit {
+            source: storage.location(lang.as_str(), entry.id),
+            entry,
+        };
+        match out.iter_mut().find(|x| x.lang == lang) {
+            Some(n) => n.hits.push(hit),
+            None => out.push(LanguageHits {
+                header: storage.header(lang.as_str()).unwrap_or_default(),
+                lang,
+                hits: vec![hit],
+            }),
+        }
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strings(values: &[&str]) -> Vec<String> {
+        values.iter().map(|x| x.to_string()).collect()
+    }
+
+    /// a dog, a cat and a dane in a language called german
+    fn vocab() -> Vec<Vocab> {
+        vec![
+            Vocab::new(
+                String::from("der Hund"),
+                strings(&["dog"]),
+                Some(strings(&["gender:m", "plural:die Hunde"])),
+                strings(&["animal"]),
+            ),
+            Vocab::new(
+                String::from("die Katze"),
+                strings(&["cat"]),
+                Some(strings(&["gender:f"])),
+                strings(&["animal"]),
+            ),
+            Vocab::new(
+                String::from("die Dogge"),
+                strings(&["great dane", "dogs (breed)"]),
+                None,
+                Vec::new(),
+            ),
+        ]
+    }
+
+    /// get the names of the vocabulary matching a query
+    fn found(query: &str) -> Vec<String> {
+        let query: Query = Query::parse(query).unwrap();
+        vocab()
+            .iter()
+            .filter(|x| query.matches(&strings(&["german"]), x))
+            .map(|x| x.get_name())
+            .collect()
+    }
+
+    #[test]
+    fn and_binds_tighter_than_or() {
+        // `a OR b AND c` is `a OR (b AND c)`
+        assert_eq!(
+            found("name:dogge OR tag:animal gender=f"),
+            strings(&["die Katze", "die Dogge"])
+        );
+        assert_eq!(
+            found("(name:dogge OR tag:animal) gender=f"),
+            strings(&["die Katze"])
+        );
+        assert_eq!(found("tag:animal AND cat"), strings(&["die Katze"]));
+        assert_eq!(found(""), strings(&["der Hund", "die Katze", "die Dogge"]));
+    }
+
+    #[test]
+    fn negation() {
+        assert_eq!(found("NOT tag:animal"), strings(&["die Dogge"]));
+        assert_eq!(
+            found("tag:animal NOT (gender=m OR lang:french)"),
+            strings(&["die Katze"])
+        );
+        assert_eq!(found("NOT NOT gender=m"), strings(&["der Hund"]));
+        // `key=` matches vocabulary having the additional
+        assert_eq!(found("NOT gender="), strings(&["die Dogge"]));
+    }
+
+    #[test]
+    fn quoting_and_words() {
+        assert_eq!(found("\"great dane\""), strings(&["die Dogge"]));
+        assert_eq!(found("great dane"), strings(&["die Dogge"]));
+        assert_eq!(found("meaning:\"(breed)\""), strings(&["die Dogge"]));
+        assert_eq!(found("dog"), strings(&["der Hund", "die Dogge"]));
+        assert_eq!(found("<dog>"), strings(&["der Hund"]));
+        assert_eq!(found("plural=\"die hunde\""), strings(&["der Hund"]));
+    }
+
+    #[test]
+    fn regex() {
+        assert_eq!(found("name:/^die /"), strings(&["die Katze", "die Dogge"]));
+        assert_eq!(found("/^d.g$/"), strings(&["der Hund"]));
+        // a slash inside a regex is escaped and whitespace doesn't end it
+        assert_eq!(found("/a\\/b|t d/"), strings(&["die Dogge"]));
+        // regular expressions are case sensitive
+        assert!(found("/DOG/").is_empty());
+    }
+
+    #[test]
+    fn invalid_queries() {
+        for query in [
+            "(dog",
+            "dog)",
+            "dog OR",
+            "AND dog",
+            "\"dog",
+            "/dog",
+            "/(/",
+            "color:red",
+            "name:",
+        ] {
+            assert!(Query::parse(query).is_err(), "{}", query);
+        }
+    }
+
+    #[test]
+    fn spans_skip_negated_terms() {
+        let query: Query = Query::parse("dog NOT name:hund").unwrap();
+        assert_eq!(
+            query.spans(Part::Meaning, "dog, dogs"),
+            vec![(0, 3), (5, 8)]
+        );
+        assert!(query.spans(Part::Name, "der Hund").is_empty());
+        let query: Query = Query::parse("gender=m").unwrap();
+        assert_eq!(query.spans(Part::Additional("Gender"), "m"), vec![(0, 1)]);
+        assert!(query.spans(Part::Tag, "m").is_empty());
+    }
 }
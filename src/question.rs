@@ -1,128 +1,828 @@
 use btui::Terminal;
 use btui::{effects::*, print::*};
 use rand::seq::SliceRandom;
-use rand::thread_rng;
-use std::io::{stdout, Write};
+use rand::{thread_rng, Rng};
+use std::collections::VecDeque;
+use std::io::{stderr, stdout, Error, Write};
 use std::process::exit;
+use std::time::{Duration, Instant};
 
+use crate::dict::Vocab;
 use crate::info;
+use crate::matching;
+use crate::normalize::Normalizer;
+use crate::review::now;
+use serde::ser::Serializer;
+use serde::Serialize;
+use serde_derive::Serialize;
 
-pub fn question_vocab(
-    lang: String,
-    vocab: Vec<crate::dict::Vocab>,
-    amount: String,
-    adds: bool,
-    clearlines: bool,
-) -> (usize, usize) {
-    let term: Terminal = Terminal::default();
-    let mut progress: usize = 0;
-    let mut add_progress: usize = 0;
-    let mut done: Vec<&crate::dict::Vocab> = Vec::new();
+/// enum representing the verdict on a single answer
+#[derive(Debug, Clone, PartialEq)]
+pub enum Verdict {
+    Correct,
+    /// the answer was close to the contained correct answer
+    Almost(String),
+    Wrong,
+}
+
+impl Verdict {
+    /// get the name of the verdict as it is stored in the history
+    pub fn as_str(&self) -> &str {
+        match self {
+            Verdict::Correct => "correct",
+            Verdict::Almost(_) => "almost",
+            Verdict::Wrong => "wrong",
+        }
+    }
+}
+
+impl Serialize for Verdict {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+/// enum representing what was asked for in a question
+#[derive(Debug, Clone, PartialEq)]
+pub enum AnswerKind {
+    Meaning,
+    /// the vocab was asked for one of its meanings (reverse direction)
+    Name,
+    Additional,
+}
+
+impl AnswerKind {
+    /// get the name of the kind as it is stored in the history
+    pub fn as_str(&self) -> &str {
+        match self {
+            AnswerKind::Meaning => "meaning",
+            AnswerKind::Name => "name",
+            AnswerKind::Additional => "additional",
+        }
+    }
+}
+
+impl Serialize for AnswerKind {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+/// a single answer given by the user
+#[derive(Debug, Clone, Serialize)]
+pub struct Answer {
+    /// the name of the vocab that was asked
+    pub vocab: String,
+    /// the answer as it was typed
+    pub input: String,
+    pub verdict: Verdict,
+    pub kind: AnswerKind,
+    /// unix timestamp of the answer
+    pub time: i64,
+}
+
+/// the result of questioning a single vocabulary
+#[derive(Debug, Clone, Serialize)]
+pub struct CardResult {
+    pub vocab: Vocab,
+    /// the verdict on the meanings of the vocab
+    pub verdict: Verdict,
+}
+
+/// the result of a learning session
+#[derive(Debug, Clone)]
+pub struct SessionResult {
+    /// amount of correctly answered vocabs
+    pub correct: usize,
+    /// amount of almost correctly answered vocabs
+    pub almost: usize,
+    /// amount of correctly answered additionals
+    pub add_correct: usize,
+    /// amount of almost correctly answered additionals
+    pub add_almost: usize,
+    /// amount of vocabs asked again after an answer that wasn't correct (see
+    /// `QuestionOptions::learning_loop`)
+    pub retries: usize,
+    /// amount of vocabs still waiting to be asked (again) when the time was up
+    pub unanswered: usize,
+    /// the result of every vocab in the order they were asked
+    pub cards: Vec<CardResult>,
+    /// every answer in the order they were given
+    pub answers: Vec<Answer>,
+}
+
+/// the results of the additionals of a learning session
+#[derive(Debug, Clone, Serialize)]
+pub struct AdditionalsReport {
+    /// amount of additionals asked
+    pub total: usize,
+    pub correct: usize,
+    pub almost: usize,
+    /// percentage of correct answers (almost correct answers count half). `None` if the vocabulary
+    /// asked had no additionals
+    pub score: Option<f32>,
+}
+
+/// summary of a learning session as it is shown at the end of the session
+#[derive(Debug, Clone, Serialize)]
+pub struct SessionReport {
+    pub language: String,
+    /// amount of vocabs asked
+    pub total: usize,
+    /// amount of vocabs answered correctly (on the first try if they were asked again)
+    pub correct: usize,
+    pub almost: usize,
+    pub retries: usize,
+    /// percentage of correct answers (almost correct answers count half)
+    pub score: f32,
+    /// the results of the additionals (`None` if they weren't asked)
+    pub additionals: Option<AdditionalsReport>,
+    pub cards: Vec<CardResult>,
+    pub answers: Vec<Answer>,
+}
+
+impl SessionReport {
+    /// summarize a learning session
+    /// # Arguments
+    /// * `lang`: the language learned
+    /// * `session`: the result of the session
+    /// * `adds`: whether additionals were asked
+    pub fn new(lang: &str, session: &SessionResult, adds: bool) -> SessionReport {
+        let score = |correct: usize, almost: usize, total: usize| -> f32 {
+            // almost correct answers count half
+            ((correct as f32 + almost as f32 * 0.5) / total as f32) * 100.0
+        };
+        let add_total: usize = session
+            .cards
+            .iter()
+            .map(|x| x.vocab.get_additionals().map(|n| n.len()).unwrap_or(0))
+            .sum();
+        SessionReport {
+            language: lang.to_string(),
+            total: session.cards.len(),
+            correct: session.correct,
+            almost: session.almost,
+            retries: session.retries,
+            score: score(session.correct, session.almost, session.cards.len()),
+            additionals: if adds {
+                Some(AdditionalsReport {
+                    total: add_total,
+                    correct: session.add_correct,
+                    almost: session.add_almost,
+                    score: (add_total > 0)
+                        .then(|| score(session.add_correct, session.add_almost, add_total)),
+                })
+            } else {
+                None
+            },
+            cards: session.cards.clone(),
+            answers: session.answers.clone(),
+        }
+    }
+}
+
+/// enum representing the direction vocabulary is asked in
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Direction {
+    /// ask for the meanings of a vocab
+    Forward,
+    /// ask for the vocab of a meaning
+    Reverse,
+    /// randomly choose the direction for every vocab
+    Mixed,
+}
+
+/// settings of a learning session
+#[derive(Debug, Clone)]
+pub struct QuestionOptions {
+    /// how many meanings have to be answered per vocab (`one` or `all`)
+    pub amount: String,
+    /// whether additionals are asked
+    pub adds: bool,
+    /// whether unused lines are cleared
+    pub clearlines: bool,
+    /// the tolerated edits per character for an answer to count as almost correct
+    pub tolerance: f32,
+    /// the normalizer applied to answers and meanings before comparing them
+    pub normalizer: Normalizer,
+    pub direction: Direction,
+    /// whether questions are asked as multiple choice
+    pub choice: bool,
+    /// whether wrongly or almost correctly answered vocabs are asked again until they are answered
+    /// correctly
+    pub learning_loop: bool,
+    /// whether the vocabulary is asked in a random order instead of the given one
+    pub shuffle: bool,
+    /// the maximum duration of the session in minutes
+    pub minutes: Option<u64>,
+    /// the title of the language shown when the session starts (e.g. `German → English`)
+    pub title: String,
+    /// whether questions and verdicts are written to stderr because stdout is kept for the
+    /// results (e.g. with `--format json`)
+    pub stderr: bool,
+}
+
+/// the terminal questions are asked on. The questions, verdicts and cursor movements go to stdout
+/// or to stderr if stdout is kept for the results
+pub struct Prompt {
+    term: Terminal,
+    stderr: bool,
+}
+
+impl Prompt {
+    /// create a prompt
+    /// # Arguments
+    /// * `stderr`: whether to write to stderr instead of stdout
+    pub fn new(stderr: bool) -> Prompt {
+        Prompt {
+            term: Terminal::default(),
+            stderr,
+        }
+    }
+
+    /// the terminal (e.g. to print warnings and errors)
+    pub fn terminal(&self) -> &Terminal {
+        &self.term
+    }
+
+    /// write text without a newline
+    pub fn print<T: std::fmt::Display>(&self, content: T) -> Result<(), Error> {
+        if self.stderr {
+            self.term.eprint(content)
+        } else {
+            self.term.print(content)
+        }
+    }
+
+    /// write text followed by a newline
+    pub fn println<T: std::fmt::Display>(&self, content: T) -> Result<(), Error> {
+        self.print(format!("{}\n", content))
+    }
+
+    /// make sure everything written is shown (e.g. before waiting for input)
+    pub fn flush(&self) -> Result<(), Error> {
+        if self.stderr {
+            stderr().flush()
+        } else {
+            stdout().flush()
+        }
+    }
+
+    /// read a line without the whitespace at its end
+    pub fn read_line_trimmed(&self) -> Result<String, Error> {
+        self.term.read_line_trimmed()
+    }
+
+    /// move the cursor up (negative `y`) or down
+    pub fn move_cursor(&self, y: i32) -> Result<(), Error> {
+        match y {
+            n if n < 0 => self.print(format!("\x1b[{}A", -n)),
+            n if n > 0 => self.print(format!("\x1b[{}B", n)),
+            _ => Ok(()),
+        }
+    }
+
+    /// move the cursor to a column of its line
+    pub fn set_cursor_x(&self, x: usize) -> Result<(), Error> {
+        self.print(format!("\x1b[{}G", x))
+    }
+
+    /// clear the line of the cursor
+    pub fn clear_line(&self) -> Result<(), Error> {
+        self.print("\x1b[2K")
+    }
+}
+
+/// the amount of vocabs asked before a wrongly answered vocab is asked again
+const REQUEUE_DISTANCE: usize = 3;
+
+/// the amount of choices in a multiple choice question
+const CHOICES: usize = 4;
+
+/// judge an answer after normalizing it and the accepted answers
+/// # Arguments
+/// * `input`: the answer as it was typed
+/// * `accepted`: the accepted answers as they are stored
+/// * `options`: the settings of the session
+/// # Returns
+/// the verdict. An almost correct answer contains the accepted answer as it is stored
+fn judge(input: &str, accepted: &[String], options: &QuestionOptions) -> Verdict {
+    let normalized: Vec<String> = accepted
+        .iter()
+        .map(|x| options.normalizer.normalize(x))
+        .collect();
+    match matching::judge(
+        options.normalizer.normalize(input).as_str(),
+        &normalized,
+        options.tolerance,
+    ) {
+        Verdict::Almost(n) => match normalized.iter().position(|x| x == &n) {
+            Some(idx) => Verdict::Almost(accepted[idx].clone()),
+            None => Verdict::Almost(n),
+        },
+        n => n,
+    }
+}
+
+/// build the choices of a multiple choice question
+/// # Arguments
+/// * `correct`: the correct choice
+/// * `pool`: the pool to draw distractors from
+/// * `exclude`: answers that would be correct as well and must not be drawn as distractors
+/// * `options`: the settings of the session
+/// # Returns
+/// the shuffled choices and the index of the correct one
+fn build_choices(
+    correct: &str,
+    pool: Vec<String>,
+    exclude: &[String],
+    options: &QuestionOptions,
+) -> (Vec<String>, usize) {
+    let mut seen: Vec<String> = exclude
+        .iter()
+        .map(|x| options.normalizer.normalize(x))
+        .collect();
+    seen.push(options.normalizer.normalize(correct));
+    let mut distractors: Vec<String> = Vec::new();
+    for candidate in pool {
+        let normalized: String = options.normalizer.normalize(candidate.as_str());
+        if !seen.contains(&normalized) {
+            seen.push(normalized);
+            distractors.push(candidate);
+        }
+    }
+    distractors.shuffle(&mut thread_rng());
+    let mut choices: Vec<String> = distractors.into_iter().take(CHOICES - 1).collect();
+    choices.push(correct.to_string());
+    choices.shuffle(&mut thread_rng());
+    let idx: usize = choices.iter().position(|x| x == correct).unwrap_or(0);
+    (choices, idx)
+}
+
+/// get all values an additional has in a set of vocabulary
+/// # Arguments
+/// * `vocab`: the vocabulary to search
+/// * `key`: the key of the additional
+/// # Returns
+/// the distinct values sorted alphabetically
+fn additional_values(vocab: &[Vocab], key: &str) -> Vec<String> {
+    let mut values: Vec<String> = Vec::new();
+    for voc in vocab {
+        for add in voc.get_additionals().unwrap_or_default() {
+            let mut parts = add.splitn(2, ':');
+            if parts.next() != Some(key) {
+                continue;
+            }
+            if let Some(n) = parts.next() {
+                if !values.iter().any(|x| x == n) {
+                    values.push(n.to_string());
+                }
+            }
+        }
+    }
+    values.sort();
+    values
+}
+
+/// ask a multiple choice question. The user answers with the number of a choice
+/// # Arguments
+/// * `term`: the terminal to ask on
+/// * `question`: the question to ask
+/// * `choices`: the choices to choose from
+/// * `correct`: the index of the correct choice
+/// * `options`: the settings of the session
+/// # Returns
+/// the chosen answer and the verdict on it
+fn question_choice(
+    term: &Prompt,
+    question: String,
+    choices: &[String],
+    correct: usize,
+    options: &QuestionOptions,
+) -> (String, Verdict) {
     term.println(format!(
-        "{}You will be learning {} {} vocabularies{}",
-        fg(Color::Green),
-        vocab.len(),
-        lang,
+        "{}{}{}",
+        fg(Color::White),
+        question,
         sp(Special::Reset)
     ))
     .unwrap();
-    while done.len() != vocab.len() {
-        let mut cur_vocab = match vocab.choose(&mut thread_rng()) {
-            Some(n) => n,
-            None => {
-                return (0usize, 0usize);
+    for (idx, choice) in choices.iter().enumerate() {
+        term.println(format!(
+            "  {}{}) {}{}",
+            fg(Color::Blue),
+            idx + 1,
+            choice,
+            sp(Special::Reset)
+        ))
+        .unwrap();
+    }
+    let mut invalid: usize = 0;
+    let chosen: usize = loop {
+        term.print(format!(
+            "{}(1-{}) > {}",
+            fg(Color::White),
+            choices.len(),
+            sp(Special::Reset)
+        ))
+        .unwrap();
+        if let Err(e) = term.flush() {
+            info::print_info(
+                term.terminal(),
+                format!("error when flushing the terminal: {}", e),
+                info::MessageType::Error,
+            );
+            exit(1);
+        }
+        let input: String = term.read_line_trimmed().unwrap();
+        match input.parse::<usize>() {
+            Ok(n) if n >= 1 && n <= choices.len() => break n - 1,
+            _ => {
+                invalid += 1;
+                term.println(format!(
+                    "{}'{}' is not a valid choice{}",
+                    fg(Color::Red),
+                    input,
+                    sp(Special::Reset)
+                ))
+                .unwrap();
             }
-        };
-        while done.contains(&cur_vocab) {
-            cur_vocab = match vocab.choose(&mut thread_rng()) {
-                Some(n) => n,
-                None => {
-                    return (0usize, 0usize);
-                }
-            };
         }
-        let meanings = cur_vocab.get_meanings().len();
-        let mut meanings_done_count = 0usize;
-        let mut meanings_done: Vec<String> = Vec::new();
-        let mut so = stdout();
-        while meanings != meanings_done_count {
-            term.print(format!(
-                "{}what does '{}' mean? ({}/{})? > {}",
+    };
+
+    // clear the screen if needed
+    if options.clearlines {
+        for _ in 0..(choices.len() + 2 + invalid * 2) {
+            term.move_cursor(-1).unwrap();
+            term.clear_line().unwrap();
+        }
+        term.set_cursor_x(1).unwrap();
+    }
+    if chosen == correct {
+        term.println(format!(
+            "{}correct!{}",
+            fg(Color::Green),
+            sp(Special::Reset)
+        ))
+        .unwrap();
+        (choices[chosen].clone(), Verdict::Correct)
+    } else {
+        term.println(format!(
+            "{}wrong! {}{}'{}'{}{} would have been right{}",
+            fg(Color::Red),
+            fg(Color::White),
+            sp(Special::Bold),
+            choices[correct],
+            sp(Special::Reset),
+            fg(Color::Red),
+            sp(Special::Reset)
+        ))
+        .unwrap();
+        (choices[chosen].clone(), Verdict::Wrong)
+    }
+}
+
+/// ask for the vocab of one of its meanings. Every vocab of the session that has the asked meaning
+/// is accepted as an answer
+/// # Arguments
+/// * `term`: the terminal to ask on
+/// * `lang`: the language that is learned
+/// * `cur_vocab`: the vocab to ask for
+/// * `vocab`: all vocabulary of the session
+/// * `options`: the settings of the session
+/// # Returns
+/// the answer as it was typed and the verdict on it
+fn question_reverse(
+    term: &Prompt,
+    lang: &str,
+    cur_vocab: &Vocab,
+    vocab: &[Vocab],
+    options: &QuestionOptions,
+) -> (String, Verdict) {
+    let meaning: String = match cur_vocab.get_meanings().choose(&mut thread_rng()) {
+        Some(n) => n.clone(),
+        None => String::new(),
+    };
+    let normalized: String = options.normalizer.normalize(meaning.as_str());
+    let accepted: Vec<String> = vocab
+        .iter()
+        .filter(|x| {
+            x.get_meanings()
+                .iter()
+                .any(|m| options.normalizer.normalize(m) == normalized)
+        })
+        .map(|x| x.get_name())
+        .collect();
+    if options.choice {
+        let pool: Vec<String> = vocab.iter().map(|x| x.get_name()).collect();
+        let (choices, correct) = build_choices(&cur_vocab.get_name(), pool, &accepted, options);
+        return question_choice(
+            term,
+            format!("which {} word means '{}'?", lang, meaning),
+            &choices,
+            correct,
+            options,
+        );
+    }
+    term.print(format!(
+        "{}which {} word means '{}'? > {}",
+        fg(Color::White),
+        lang,
+        meaning,
+        sp(Special::Reset)
+    ))
+    .unwrap();
+    if let Err(e) = term.flush() {
+        info::print_info(
+            term.terminal(),
+            format!("error when flushing the terminal: {}", e),
+            info::MessageType::Error,
+        );
+        exit(1);
+    }
+    let input: String = term.read_line_trimmed().unwrap();
+
+    // clear the screen if needed
+    if options.clearlines {
+        term.move_cursor(-1).unwrap();
+        term.clear_line().unwrap();
+        term.move_cursor(-1).unwrap();
+        term.clear_line().unwrap();
+        term.set_cursor_x(1).unwrap();
+    }
+    let verdict: Verdict = judge(input.as_str(), &accepted, options);
+    match &verdict {
+        Verdict::Correct => {
+            term.println(format!(
+                "{}correct!{}",
+                fg(Color::Green),
+                sp(Special::Reset)
+            ))
+            .unwrap();
+        }
+        Verdict::Almost(n) => {
+            term.println(format!(
+                "{}almost! did you mean {}{}'{}'{}{}?{}",
+                fg(Color::Yellow),
                 fg(Color::White),
-                cur_vocab.get_name(),
-                meanings_done_count,
-                meanings,
+                sp(Special::Bold),
+                n,
+                sp(Special::Reset),
+                fg(Color::Yellow),
                 sp(Special::Reset)
             ))
             .unwrap();
-            let _ = match so.flush() {
-                Ok(_) => (),
-                Err(e) => {
-                    info::print_info(
-                        &term,
-                        format!("error when flushing stdout: {}", e),
-                        info::MessageType::Error,
-                    );
-                    exit(1);
-                }
-            };
-            let input: String = term.read_line_trimmed().unwrap();
-            let captured: String = input.as_str().to_lowercase();
-
-            // clear the screen if needed
-            if clearlines {
-                term.move_cursor(0, -1).unwrap();
-                term.clear_line().unwrap();
-                term.move_cursor(0, -1).unwrap();
-                //term.clear_line().unwrap();
-                term.clear_line().unwrap();
-                term.set_cursor_x(1).unwrap();
-            }
-            if meanings_done.contains(&captured) {
+        }
+        Verdict::Wrong => {
+            term.println(format!(
+                "{}wrong! {}{}{:?}{} would have been right{}",
+                fg(Color::Red),
+                fg(Color::White),
+                sp(Special::Bold),
+                accepted.join(", "),
+                fg(Color::Red),
+                sp(Special::Reset)
+            ))
+            .unwrap();
+        }
+    }
+    (input, verdict)
+}
+
+pub fn question_vocab(lang: String, vocab: Vec<Vocab>, options: &QuestionOptions) -> SessionResult {
+    let term: Prompt = Prompt::new(options.stderr);
+    let amount: &str = options.amount.as_str();
+    let clearlines: bool = options.clearlines;
+    let mut progress: usize = 0;
+    let mut almost: usize = 0;
+    let mut add_progress: usize = 0;
+    let mut add_almost: usize = 0;
+    let mut retries: usize = 0;
+    let mut unanswered: usize = 0;
+    let mut asked: Vec<usize> = Vec::new();
+    let started: Instant = Instant::now();
+    let mut queue: Vec<usize> = (0..vocab.len()).collect();
+    if options.shuffle {
+        queue.shuffle(&mut thread_rng());
+    }
+    let mut queue: VecDeque<usize> = queue.into_iter().collect();
+    let mut cards: Vec<CardResult> = Vec::new();
+    let mut answers: Vec<Answer> = Vec::new();
+    term.println(format!(
+        "{}You will be learning {} {} vocabularies{}",
+        fg(Color::Green),
+        vocab.len(),
+        options.title,
+        sp(Special::Reset)
+    ))
+    .unwrap();
+    while let Some(card_idx) = queue.pop_front() {
+        if let Some(n) = options.minutes {
+            if started.elapsed() >= Duration::from_secs(n * 60) {
                 term.println(format!(
-                    "{}already used{}",
-                    fg(Color::Red),
+                    "{}time is up!{}",
+                    fg(Color::Yellow),
                     sp(Special::Reset)
                 ))
                 .unwrap();
-                continue;
+                // the vocab just taken from the queue wasn't asked either
+                unanswered = queue.len() + 1;
+                break;
             }
-            if cur_vocab.get_meanings().contains(&captured) {
-                term.println(format!(
-                    "{}correct!{}",
-                    fg(Color::Green),
+        }
+        let cur_vocab: &Vocab = &vocab[card_idx];
+        let first_try: bool = !asked.contains(&card_idx);
+        if first_try {
+            asked.push(card_idx);
+        } else {
+            retries += 1;
+        }
+        let meanings = cur_vocab.get_meanings().len();
+        let mut meanings_done_count = 0usize;
+        let mut meanings_done: Vec<String> = Vec::new();
+        let mut card_verdict: Verdict = Verdict::Wrong;
+        let mut card_almost: Option<String> = None;
+        let reverse: bool = match options.direction {
+            Direction::Forward => false,
+            Direction::Reverse => true,
+            Direction::Mixed => thread_rng().gen_bool(0.5),
+        };
+        if reverse {
+            let (input, verdict) = question_reverse(&term, &lang, cur_vocab, &vocab, options);
+            answers.push(Answer {
+                vocab: cur_vocab.get_name(),
+                input,
+                verdict: verdict.clone(),
+                kind: AnswerKind::Name,
+                time: now(),
+            });
+            card_verdict = verdict;
+        } else if options.choice {
+            let meaning: String = match cur_vocab.get_meanings().choose(&mut thread_rng()) {
+                Some(n) => n.clone(),
+                None => String::new(),
+            };
+            let pool: Vec<String> = vocab
+                .iter()
+                .filter(|x| x.get_name() != cur_vocab.get_name())
+                .flat_map(|x| x.get_meanings())
+                .collect();
+            let (choices, correct) =
+                build_choices(&meaning, pool, &cur_vocab.get_meanings(), options);
+            let (input, verdict) = question_choice(
+                &term,
+                format!("what does '{}' mean?", cur_vocab.get_name()),
+                &choices,
+                correct,
+                options,
+            );
+            answers.push(Answer {
+                vocab: cur_vocab.get_name(),
+                input,
+                verdict: verdict.clone(),
+                kind: AnswerKind::Meaning,
+                time: now(),
+            });
+            card_verdict = verdict;
+        } else {
+            while meanings != meanings_done_count {
+                term.print(format!(
+                    "{}what does '{}' mean? ({}/{})? > {}",
+                    fg(Color::White),
+                    cur_vocab.get_name(),
+                    meanings_done_count,
+                    meanings,
                     sp(Special::Reset)
                 ))
                 .unwrap();
-                if amount == *"one" {
-                    progress += 1;
-                    break;
+                match term.flush() {
+                    Ok(_) => (),
+                    Err(e) => {
+                        info::print_info(
+                            term.terminal(),
+                            format!("error when flushing the terminal: {}", e),
+                            info::MessageType::Error,
+                        );
+                        exit(1);
+                    }
+                };
+                let input: String = term.read_line_trimmed().unwrap();
+
+                // clear the screen if needed
+                if clearlines {
+                    term.move_cursor(-1).unwrap();
+                    term.clear_line().unwrap();
+                    term.move_cursor(-1).unwrap();
+                    //term.clear_line().unwrap();
+                    term.clear_line().unwrap();
+                    term.set_cursor_x(1).unwrap();
                 }
-                meanings_done_count += 1;
-                meanings_done.push(captured);
-            } else {
-                let mut correct_meanings_string: String = cur_vocab.get_meanings()[0].clone();
-                for meaning in cur_vocab.get_meanings()[1..].to_vec() {
-                    correct_meanings_string.push_str(format!(", {}", meaning).as_str());
+                if judge(input.as_str(), &meanings_done, options) == Verdict::Correct {
+                    term.println(format!(
+                        "{}already used{}",
+                        fg(Color::Red),
+                        sp(Special::Reset)
+                    ))
+                    .unwrap();
+                    continue;
                 }
-                term.println(format!(
-                    "{}wrong! {}{}{:?}{} would have been right{}",
-                    fg(Color::Red),
-                    fg(Color::White),
-                    sp(Special::Bold),
-                    correct_meanings_string,
-                    fg(Color::Red),
-                    sp(Special::Reset)
-                ))
-                .unwrap();
-                break;
+                let remaining: Vec<String> = cur_vocab
+                    .get_meanings()
+                    .into_iter()
+                    .filter(|x| !meanings_done.contains(x))
+                    .collect();
+                let verdict: Verdict = judge(input.as_str(), &remaining, options);
+                answers.push(Answer {
+                    vocab: cur_vocab.get_name(),
+                    input: input.clone(),
+                    verdict: verdict.clone(),
+                    kind: AnswerKind::Meaning,
+                    time: now(),
+                });
+                if verdict == Verdict::Correct {
+                    term.println(format!(
+                        "{}correct!{}",
+                        fg(Color::Green),
+                        sp(Special::Reset)
+                    ))
+                    .unwrap();
+                    if amount == "one" {
+                        card_verdict = Verdict::Correct;
+                        break;
+                    }
+                    meanings_done_count += 1;
+                    let normalized: String = options.normalizer.normalize(input.as_str());
+                    if let Some(n) = remaining
+                        .into_iter()
+                        .find(|x| options.normalizer.normalize(x) == normalized)
+                    {
+                        meanings_done.push(n);
+                    }
+                } else if let Verdict::Almost(meaning) = verdict {
+                    term.println(format!(
+                        "{}almost! did you mean {}{}'{}'{}{}?{}",
+                        fg(Color::Yellow),
+                        fg(Color::White),
+                        sp(Special::Bold),
+                        meaning,
+                        sp(Special::Reset),
+                        fg(Color::Yellow),
+                        sp(Special::Reset)
+                    ))
+                    .unwrap();
+                    if amount == "one" {
+                        card_verdict = Verdict::Almost(meaning);
+                        break;
+                    }
+                    meanings_done_count += 1;
+                    meanings_done.push(meaning.clone());
+                    if card_almost.is_none() {
+                        card_almost = Some(meaning);
+                    }
+                } else {
+                    let mut correct_meanings_string: String = cur_vocab.get_meanings()[0].clone();
+                    for meaning in cur_vocab.get_meanings()[1..].to_vec() {
+                        correct_meanings_string.push_str(format!(", {}", meaning).as_str());
+                    }
+                    term.println(format!(
+                        "{}wrong! {}{}{:?}{} would have been right{}",
+                        fg(Color::Red),
+                        fg(Color::White),
+                        sp(Special::Bold),
+                        correct_meanings_string,
+                        fg(Color::Red),
+                        sp(Special::Reset)
+                    ))
+                    .unwrap();
+                    break;
+                }
+            }
+            if meanings == meanings_done_count {
+                card_verdict = match card_almost {
+                    Some(n) => Verdict::Almost(n),
+                    None => Verdict::Correct,
+                };
             }
         }
-        if meanings == meanings_done_count {
-            progress += 1;
+        // an almost correct answer isn't correct yet, so the vocab is asked again as well
+        if options.learning_loop && card_verdict != Verdict::Correct {
+            queue.insert(REQUEUE_DISTANCE.min(queue.len()), card_idx);
+        }
+        // only the first try counts for the result, retries only ask the meanings again
+        if !first_try {
+            continue;
+        }
+        match card_verdict {
+            Verdict::Correct => progress += 1,
+            Verdict::Almost(_) => almost += 1,
+            Verdict::Wrong => (),
         }
-        if !adds {
-            done.push(cur_vocab);
+        cards.push(CardResult {
+            vocab: cur_vocab.clone(),
+            verdict: card_verdict,
+        });
+        if !options.adds {
             continue;
         }
         if let Some(adds) = cur_vocab.get_additionals() {
@@ -133,7 +833,7 @@ pub fn question_vocab(
                     Some(n) => n,
                     None => {
                         info::print_info(
-                            &term,
+                            term.terminal(),
                             format!(
                                 "failed to parse additional from vocab '{}': {}",
                                 cur_vocab.get_name(),
@@ -144,11 +844,11 @@ pub fn question_vocab(
                         exit(1);
                     }
                 };
-                let value = match adds[idx].split(':').nth(1) {
+                let value = match adds[idx].split_once(':').map(|x| x.1) {
                     Some(n) => n,
                     None => {
                         info::print_info(
-                            &term,
+                            term.terminal(),
                             format!(
                                 "failed to parse additional from vocab '{}': {}",
                                 cur_vocab.get_name(),
@@ -159,6 +859,34 @@ pub fn question_vocab(
                         exit(1);
                     }
                 };
+                let values: Vec<String> = additional_values(&vocab, key);
+                if options.choice && values.len() > 1 && values.len() <= CHOICES {
+                    let correct: usize = values.iter().position(|x| x == value).unwrap_or(0);
+                    let (input, verdict) = question_choice(
+                        &term,
+                        format!(
+                            "(additional) what is '{}' of '{}'?",
+                            key,
+                            cur_vocab.get_name()
+                        ),
+                        &values,
+                        correct,
+                        options,
+                    );
+                    if verdict == Verdict::Correct {
+                        add_progress += 1;
+                    }
+                    answers.push(Answer {
+                        vocab: cur_vocab.get_name(),
+                        input,
+                        verdict,
+                        kind: AnswerKind::Additional,
+                        time: now(),
+                    });
+                    adds_done.push(adds[idx].clone());
+                    idx += 1;
+                    continue;
+                }
                 term.print(format!(
                     "{}(additional) what is '{}' of '{}'? > {}",
                     fg(Color::White),
@@ -167,31 +895,38 @@ pub fn question_vocab(
                     sp(Special::Reset)
                 ))
                 .unwrap();
-                let _ = match so.flush() {
+                match term.flush() {
                     Ok(_) => (),
                     Err(e) => {
                         info::print_info(
-                            &term,
-                            format!("error when flushing stdout: {}", e),
+                            term.terminal(),
+                            format!("error when flushing the terminal: {}", e),
                             info::MessageType::Error,
                         );
                         exit(1);
                     }
                 };
                 let input: String = term.read_line_trimmed().unwrap();
-                let captured: String = input.as_str().to_lowercase();
                 //
                 // clear the screen if needed
                 if clearlines {
                     term.clear_line().unwrap();
-                    term.move_cursor(0, -1).unwrap();
+                    term.move_cursor(-1).unwrap();
                     term.clear_line().unwrap();
-                    term.move_cursor(0, -1).unwrap();
+                    term.move_cursor(-1).unwrap();
                     term.clear_line().unwrap();
                     term.set_cursor_x(1).unwrap();
                 }
 
-                if captured == value {
+                let verdict: Verdict = judge(input.as_str(), &[value.to_string()], options);
+                answers.push(Answer {
+                    vocab: cur_vocab.get_name(),
+                    input: input.clone(),
+                    verdict: verdict.clone(),
+                    kind: AnswerKind::Additional,
+                    time: now(),
+                });
+                if verdict == Verdict::Correct {
                     adds_done.push(adds[idx].clone());
                     add_progress += 1;
                     term.println(format!(
@@ -200,6 +935,20 @@ pub fn question_vocab(
                         sp(Special::Reset)
                     ))
                     .unwrap();
+                } else if let Verdict::Almost(n) = verdict {
+                    adds_done.push(adds[idx].clone());
+                    add_almost += 1;
+                    term.println(format!(
+                        "{}almost! did you mean {}{}'{}'{}{}?{}",
+                        fg(Color::Yellow),
+                        fg(Color::White),
+                        sp(Special::Bold),
+                        n,
+                        sp(Special::Reset),
+                        fg(Color::Yellow),
+                        sp(Special::Reset)
+                    ))
+                    .unwrap();
                 } else {
                     term.println(format!(
                         "{}Wrong! {}{}'{}'{}{} would have been right.{}",
@@ -217,8 +966,16 @@ pub fn question_vocab(
                 idx += 1;
             }
         }
-        done.push(cur_vocab);
     }
 
-    (progress, add_progress)
+    SessionResult {
+        correct: progress,
+        almost,
+        add_correct: add_progress,
+        add_almost,
+        retries,
+        unanswered,
+        cards,
+        answers,
+    }
 }
@@ -0,0 +1,367 @@
+use crate::db;
+use crate::dict::Vocab;
+use crate::error::*;
+use crate::question::{CardResult, Verdict};
+use rusqlite::{params, Connection, OptionalExtension};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// seconds in a day
+const DAY: i64 = 86400;
+
+/// the quality (SM-2 scale from 0 to 5) assigned to a correct answer
+const QUALITY_CORRECT: u8 = 4;
+/// the quality (SM-2 scale from 0 to 5) assigned to an almost correct answer
+const QUALITY_ALMOST: u8 = 3;
+/// the quality (SM-2 scale from 0 to 5) assigned to a wrong answer
+const QUALITY_WRONG: u8 = 1;
+
+/// the review cadence (in days) of every box in the leitner system
+const LEITNER_INTERVALS: [i64; 5] = [1, 2, 4, 8, 16];
+
+/// enum representing the algorithms available for scheduling reviews
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Scheduler {
+    /// the SM-2 spaced repetition algorithm
+    Sm2,
+    /// the leitner system
+    Leitner,
+}
+
+impl Scheduler {
+    /// parse a scheduler from its name (`sm2` or `leitner`)
+    pub fn from_name(name: &str) -> Option<Scheduler> {
+        match name {
+            "sm2" => Some(Scheduler::Sm2),
+            "leitner" => Some(Scheduler::Leitner),
+            _ => None,
+        }
+    }
+}
+
+/// get the current time as a unix timestamp
+pub fn now() -> i64 {
+    match SystemTime::now().duration_since(UNIX_EPOCH) {
+        Ok(n) => n.as_secs() as i64,
+        Err(_) => 0,
+    }
+}
+
+/// struct representing the review state of a single vocabulary according to the SM-2 algorithm
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReviewState {
+    /// the ease factor (never below 1.3)
+    pub ease: f64,
+    /// the current interval in days
+    pub interval: i64,
+    /// the amount of successful reviews in a row
+    pub repetitions: i64,
+    /// unix timestamp of the next review
+    pub due: i64,
+}
+
+impl ReviewState {
+    /// create the review state of a vocabulary that was never reviewed. It is due immediately
+    pub fn new() -> ReviewState {
+        ReviewState {
+            ease: 2.5,
+            interval: 0,
+            repetitions: 0,
+            due: 0,
+        }
+    }
+
+    /// update the state after a review
+    /// # Arguments
+    /// * `quality`: how well the vocab was remembered (0 to 5, values below 3 count as a failure)
+    /// * `time`: unix timestamp of the review
+    pub fn review(&mut self, quality: u8, time: i64) {
+        let quality: u8 = quality.min(5);
+        if quality >= 3 {
+            self.interval = match self.repetitions {
+                0 => 1,
+                1 => 6,
+                _ => (self.interval as f64 * self.ease).round() as i64,
+            };
+            self.repetitions += 1;
+        } else {
+            self.repetitions = 0;
+            self.interval = 1;
+        }
+        let diff: f64 = 5.0 - quality as f64;
+        self.ease = (self.ease + 0.1 - diff * (0.08 + diff * 0.02)).max(1.3);
+        self.due = time + self.interval * DAY;
+    }
+
+    /// check whether the vocabulary has to be reviewed
+    pub fn is_due(&self, time: i64) -> bool {
+        self.due <= time
+    }
+}
+
+impl Default for ReviewState {
+    fn default() -> ReviewState {
+        ReviewState::new()
+    }
+}
+
+/// struct representing the box of a single vocabulary in the leitner system
+#[derive(Debug, Clone, PartialEq)]
+pub struct LeitnerState {
+    /// the box the vocab is in (starting at 1)
+    pub cardbox: usize,
+    /// unix timestamp of the last review
+    pub last_review: i64,
+}
+
+impl LeitnerState {
+    /// create the state of a vocabulary that was never reviewed. It is in the first box and due
+    /// immediately
+    pub fn new() -> LeitnerState {
+        LeitnerState {
+            cardbox: 1,
+            last_review: 0,
+        }
+    }
+
+    /// move the vocab up a box on a correct answer and back to the first box on a mistake. An
+    /// almost correct answer keeps the vocab in its box
+    /// # Arguments
+    /// * `verdict`: the verdict on the answer
+    /// * `time`: unix timestamp of the review
+    pub fn review(&mut self, verdict: &Verdict, time: i64) {
+        match verdict {
+            Verdict::Correct => {
+                self.cardbox = (self.cardbox + 1).min(LEITNER_INTERVALS.len());
+            }
+            Verdict::Almost(_) => (),
+            Verdict::Wrong => {
+                self.cardbox = 1;
+            }
+        }
+        self.last_review = time;
+    }
+
+    /// check whether the vocabulary has to be reviewed
+    pub fn is_due(&self, time: i64) -> bool {
+        if self.last_review == 0 {
+            return true;
+        }
+        let idx: usize = self.cardbox.clamp(1, LEITNER_INTERVALS.len()) - 1;
+        self.last_review + LEITNER_INTERVALS[idx] * DAY <= time
+    }
+}
+
+impl Default for LeitnerState {
+    fn default() -> LeitnerState {
+        LeitnerState::new()
+    }
+}
+
+/// struct managing the review states in the database. The states are stored separately from the
+/// vocabulary so they work for both dict files and the database
+pub struct ReviewStore {
+    conn: Connection,
+    scheduler: Scheduler,
+}
+
+impl ReviewStore {
+    /// open the review store (the database is created and upgraded if needed)
+    /// # Arguments
+    /// * `path`: path to the database
+    /// * `scheduler`: the algorithm used to schedule reviews
+    pub fn open(path: &str, scheduler: Scheduler) -> Result<ReviewStore, VctError> {
+        let conn: Connection = db::open(path)?;
+        Ok(ReviewStore { conn, scheduler })
+    }
+
+    /// get the SM-2 review state of a vocab. Vocabulary that was never reviewed gets a fresh state
+    pub fn sm2_state(&self, lang: &str, name: &str) -> Result<ReviewState, VctError> {
+        let state = self
+            .conn
+            .query_row(
+                "SELECT ease, interval, repetitions, due FROM review WHERE lang = ? AND name = ?",
+                params![lang, name],
+                |row| {
+                    Ok(ReviewState {
+                        ease: row.get(0)?,
+                        interval: row.get(1)?,
+                        repetitions: row.get(2)?,
+                        due: row.get(3)?,
+                    })
+                },
+            )
+            .optional();
+        match state {
+            Ok(n) => Ok(n.unwrap_or_default()),
+            Err(e) => Err(VctError::new(
+                VctErrorKind::DatabaseError,
+                format!("error reading review state: {}", e).as_str(),
+            )),
+        }
+    }
+
+    /// store the SM-2 review state of a vocab
+    pub fn set_sm2_state(
+        &self,
+        lang: &str,
+        name: &str,
+        state: &ReviewState,
+    ) -> Result<(), VctError> {
+        match self.conn.execute(
+            "INSERT OR REPLACE INTO review (lang, name, ease, interval, repetitions, due) VALUES (?, ?, ?, ?, ?, ?)",
+            params![lang, name, state.ease, state.interval, state.repetitions, state.due],
+        ) {
+            Ok(_) => Ok(()),
+            Err(e) => Err(VctError::new(
+                VctErrorKind::DatabaseError,
+                format!("error writing review state: {}", e).as_str(),
+            )),
+        }
+    }
+
+    /// get the leitner box of a vocab. Vocabulary that was never reviewed is in the first box
+    pub fn leitner_state(&self, lang: &str, name: &str) -> Result<LeitnerState, VctError> {
+        let state = self
+            .conn
+            .query_row(
+                "SELECT box, last_review FROM leitner WHERE lang = ? AND name = ?",
+                params![lang, name],
+                |row| {
+                    let cardbox: i64 = row.get(0)?;
+                    Ok(LeitnerState {
+                        cardbox: cardbox.max(1) as usize,
+                        last_review: row.get(1)?,
+                    })
+                },
+            )
+            .optional();
+        match state {
+            Ok(n) => Ok(n.unwrap_or_default()),
+            Err(e) => Err(VctError::new(
+                VctErrorKind::DatabaseError,
+                format!("error reading leitner box: {}", e).as_str(),
+            )),
+        }
+    }
+
+    /// store the leitner box of a vocab
+    pub fn set_leitner_state(
+        &self,
+        lang: &str,
+        name: &str,
+        state: &LeitnerState,
+    ) -> Result<(), VctError> {
+        match self.conn.execute(
+            "INSERT OR REPLACE INTO leitner (lang, name, box, last_review) VALUES (?, ?, ?, ?)",
+            params![lang, name, state.cardbox as i64, state.last_review],
+        ) {
+            Ok(_) => Ok(()),
+            Err(e) => Err(VctError::new(
+                VctErrorKind::DatabaseError,
+                format!("error writing leitner box: {}", e).as_str(),
+            )),
+        }
+    }
+
+    /// move the review state of a vocab to a new name or language
+    /// # Arguments
+    /// * `lang`: the old language
+    /// * `name`: the old name
+    /// * `new_lang`: the new language
+    /// * `new_name`: the new name
+    pub fn rename(
+        &self,
+        lang: &str,
+        name: &str,
+        new_lang: &str,
+        new_name: &str,
+    ) -> Result<(), VctError> {
+        for table in ["review", "leitner"] {
+            if let Err(e) = self.conn.execute(
+                format!(
+                    "UPDATE OR REPLACE {} SET lang = ?, name = ? WHERE lang = ? AND name = ?",
+                    table
+                )
+                .as_str(),
+                params![new_lang, new_name, lang, name],
+            ) {
+                return Err(VctError::new(
+                    VctErrorKind::DatabaseError,
+                    format!("error moving review state: {}", e).as_str(),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// remove the review state of a vocab
+    /// # Arguments
+    /// * `lang`: the language of the vocab
+    /// * `name`: the name of the vocab
+    pub fn forget(&self, lang: &str, name: &str) -> Result<(), VctError> {
+        for table in ["review", "leitner"] {
+            if let Err(e) = self.conn.execute(
+                format!("DELETE FROM {} WHERE lang = ? AND name = ?", table).as_str(),
+                params![lang, name],
+            ) {
+                return Err(VctError::new(
+                    VctErrorKind::DatabaseError,
+                    format!("error removing review state: {}", e).as_str(),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// check whether a vocab is due for review according to the scheduler in use
+    pub fn is_due(&self, lang: &str, name: &str, time: i64) -> Result<bool, VctError> {
+        match self.scheduler {
+            Scheduler::Sm2 => Ok(self.sm2_state(lang, name)?.is_due(time)),
+            Scheduler::Leitner => Ok(self.leitner_state(lang, name)?.is_due(time)),
+        }
+    }
+
+    /// filter vocabulary to only contain vocabs that are due for review
+    /// # Arguments
+    /// * `lang`: the language the vocabulary belongs to
+    /// * `vocab`: the vocabulary to filter
+    pub fn due(&self, lang: &str, vocab: Vec<Vocab>) -> Result<Vec<Vocab>, VctError> {
+        let time: i64 = now();
+        let mut out: Vec<Vocab> = Vec::new();
+        for voc in vocab {
+            if self.is_due(lang, voc.get_name().as_str(), time)? {
+                out.push(voc);
+            }
+        }
+        Ok(out)
+    }
+
+    /// update the review states with the results of a learning session
+    /// # Arguments
+    /// * `lang`: the language that was learned
+    /// * `results`: the results of every questioned vocab
+    pub fn record(&self, lang: &str, results: &[CardResult]) -> Result<(), VctError> {
+        let time: i64 = now();
+        for result in results {
+            let name: String = result.vocab.get_name();
+            match self.scheduler {
+                Scheduler::Sm2 => {
+                    let mut state: ReviewState = self.sm2_state(lang, name.as_str())?;
+                    let quality: u8 = match result.verdict {
+                        Verdict::Correct => QUALITY_CORRECT,
+                        Verdict::Almost(_) => QUALITY_ALMOST,
+                        Verdict::Wrong => QUALITY_WRONG,
+                    };
+                    state.review(quality, time);
+                    self.set_sm2_state(lang, name.as_str(), &state)?;
+                }
+                Scheduler::Leitner => {
+                    let mut state: LeitnerState = self.leitner_state(lang, name.as_str())?;
+                    state.review(&result.verdict, time);
+                    self.set_leitner_state(lang, name.as_str(), &state)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,75 @@
+use crate::dict::Vocab;
+use rand::seq::SliceRandom;
+use rand::thread_rng;
+use std::collections::HashMap;
+
+/// enum representing the strategies to pick the vocabulary of a session
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Strategy {
+    /// pick vocabulary in a random order
+    Random,
+    /// pick the vocabulary that was added first. This is the insertion order (the line in a dict
+    /// file or the id in the database), not the time of the last review
+    Oldest,
+    /// pick the vocabulary that was answered wrongly most often
+    MostMissed,
+    /// pick the vocabulary that was added last (the reverse insertion order)
+    Newest,
+}
+
+impl Strategy {
+    /// parse a strategy from its name (`random`, `oldest`, `most-missed` or `newest`)
+    pub fn from_name(name: &str) -> Option<Strategy> {
+        match name {
+            "random" => Some(Strategy::Random),
+            "oldest" => Some(Strategy::Oldest),
+            "most-missed" => Some(Strategy::MostMissed),
+            "newest" => Some(Strategy::Newest),
+            _ => None,
+        }
+    }
+}
+
+/// filter vocabulary by its tags
+/// # Arguments
+/// * `vocab`: the vocabulary to filter
+/// * `include`: only keep vocabs with at least one of these tags (keep all if empty)
+/// * `exclude`: remove vocabs with at least one of these tags
+pub fn filter_tags(vocab: Vec<Vocab>, include: &[String], exclude: &[String]) -> Vec<Vocab> {
+    vocab
+        .into_iter()
+        .filter(|x| {
+            let tags: Vec<String> = x.get_tags();
+            (include.is_empty() || include.iter().any(|t| tags.contains(t)))
+                && !exclude.iter().any(|t| tags.contains(t))
+        })
+        .collect()
+}
+
+/// pick the vocabulary of a session
+/// # Arguments
+/// * `vocab`: all vocabulary of the language in the order it was added (sorted by the id of its
+///   entry)
+/// * `strategy`: how to pick the vocabulary
+/// * `limit`: the maximum amount of vocabulary to pick
+/// * `missed`: how often every vocab was answered wrongly (only used by `Strategy::MostMissed`)
+/// # Returns
+/// the picked vocabulary in the order it should be asked
+pub fn select(
+    mut vocab: Vec<Vocab>,
+    strategy: Strategy,
+    limit: Option<usize>,
+    missed: &HashMap<String, usize>,
+) -> Vec<Vocab> {
+    match strategy {
+        Strategy::Random => vocab.shuffle(&mut thread_rng()),
+        Strategy::Oldest => (),
+        Strategy::MostMissed => vocab
+            .sort_by_key(|x| std::cmp::Reverse(missed.get(&x.get_name()).copied().unwrap_or(0))),
+        Strategy::Newest => vocab.reverse(),
+    }
+    if let Some(n) = limit {
+        vocab.truncate(n);
+    }
+    vocab
+}
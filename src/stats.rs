@@ -0,0 +1,64 @@
+use crate::error::*;
+use crate::history::{Accuracy, History};
+use btui::effects::{Color, Special};
+use btui::print::{fg, sp};
+
+/// format a list of accuracies as lines of `label: correct/total (percent)`. Almost correct answers
+/// are listed seperately and count half in the percentage
+fn format_section(title: &str, entries: Vec<Accuracy>) -> String {
+    let mut out: String = format!("\n{}{}:{}\n", fg(Color::Green), title, sp(Special::Reset));
+    if entries.is_empty() {
+        out.push_str("  no answers yet\n");
+        return out;
+    }
+    for entry in entries {
+        let color: Color = if entry.percent() >= 80.0 {
+            Color::Green
+        } else if entry.percent() >= 50.0 {
+            Color::Yellow
+        } else {
+            Color::Red
+        };
+        out.push_str(
+            format!(
+                "  {}{}: {}{}/{}{} ({:.2}%){}\n",
+                fg(Color::Blue),
+                entry.label,
+                fg(color),
+                entry.correct,
+                entry.total,
+                if entry.almost == 0 {
+                    String::new()
+                } else {
+                    format!(", {} almost", entry.almost)
+                },
+                entry.percent(),
+                sp(Special::Reset)
+            )
+            .as_str(),
+        );
+    }
+    out
+}
+
+/// summarise the session history
+/// # Arguments
+/// * `history`: the session history
+/// * `lang`: if set the summary is limited to this language and lists the accuracy of every vocab.
+///   Otherwise the accuracy of every language is listed
+/// # Returns
+/// the summary as a `String` ready to be printed
+pub fn stats(history: &History, lang: Option<&str>) -> Result<String, VctError> {
+    let mut out: String = String::new();
+    match lang {
+        Some(n) => {
+            out.push_str(format_section("per week", history.per_week(Some(n))?).as_str());
+            out.push_str(format_section("per vocab", history.per_vocab(n)?).as_str());
+        }
+        None => {
+            out.push_str(format_section("per language", history.per_language()?).as_str());
+            out.push_str(format_section("per week", history.per_week(None)?).as_str());
+        }
+    }
+    Ok(out)
+}
@@ -0,0 +1,635 @@
+use crate::cfg::Config;
+use crate::check::{self, Problem};
+use crate::db;
+use crate::dict::{is_ignored, parse_dict, LineError, Vocab};
+use crate::error::*;
+use crate::header::{self, is_header, Header};
+use crate::info;
+use crate::normalize::Normalizer;
+use crate::query::{language_names, walk_through_dir, Query};
+use btui::Terminal;
+use rusqlite::Connection;
+use std::collections::BTreeMap;
+use std::fs::{create_dir_all, read_to_string, remove_file, write, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+
+/// a vocab together with its id in a storage
+#[derive(Debug, Clone, PartialEq)]
+pub struct Entry {
+    /// the id of the vocab (the line in a dict file or the id of the entry in the database)
+    pub id: i64,
+    pub vocab: Vocab,
+}
+
+/// trait implemented by everything vocabulary can be stored in
+pub trait Storage {
+    /// list all languages
+    fn languages(&self) -> Result<Vec<String>, VctError>;
+
+    /// load the vocabulary of a language in the order it was added
+    /// # Arguments
+    /// * `lang`: the language
+    fn load(&self, lang: &str) -> Result<Vec<Entry>, VctError>;
+
+    /// add a vocab to a language (the language is created if it doesn't exist)
+    /// # Arguments
+    /// * `lang`: the language
+    /// * `vocab`: the vocab to add
+    /// # Returns
+    /// the id of the new vocab
+    fn insert(&mut self, lang: &str, vocab: &Vocab) -> Result<i64, VctError>;
+
+    /// replace a vocab of a language
+    /// # Arguments
+    /// * `lang`: the language
+    /// * `id`: the id of the vocab to replace
+    /// * `vocab`: the new vocab
+    fn update(&mut self, lang: &str, id: i64, vocab: &Vocab) -> Result<(), VctError>;
+
+    /// remove a vocab from a language
+    /// # Arguments
+    /// * `lang`: the language
+    /// * `id`: the id of the vocab to remove
+    fn delete(&mut self, lang: &str, id: i64) -> Result<(), VctError>;
+
+    /// remove a language with all of its vocabulary
+    /// # Arguments
+    /// * `lang`: the language
+    fn remove_language(&mut self, lang: &str) -> Result<(), VctError>;
+
+    /// describe where a vocab is stored
+    /// # Arguments
+    /// * `lang`: the language
+    /// * `id`: the id of the vocab
+    fn location(&self, lang: &str, id: i64) -> String {
+        format!("{} @{}", lang, id)
+    }
+
+    /// whether the id of a vocab stays the same when other vocabulary is changed, so it can be used
+    /// to address the vocab (e.g. `vct rm <lang> @<id>`)
+    fn stable_ids(&self) -> bool {
+        true
+    }
+
+    /// get the metadata of a language
+    /// # Arguments
+    /// * `lang`: the language
+    /// # Returns
+    /// the header of the language (empty if it has none or doesn't exist)
+    fn header(&self, lang: &str) -> Result<Header, VctError>;
+
+    /// replace the metadata of a language (the language is created if it doesn't exist)
+    /// # Arguments
+    /// * `lang`: the language
+    /// * `header`: the new header
+    fn set_header(&mut self, lang: &str, header: &Header) -> Result<(), VctError>;
+
+    /// search all languages for vocabulary matching a query
+    /// # Arguments
+    /// * `query`: the query
+    /// # Returns
+    /// the language and entry of every vocab found. A language that can't be loaded stops the
+    /// search (its vocabulary could contain matches)
+    fn search(&self, query: &Query) -> Result<Vec<(String, Entry)>, VctError> {
+        let mut out: Vec<(String, Entry)> = Vec::new();
+        for lang in self.languages()? {
+            let entries: Vec<Entry> = self.load(lang.as_str())?;
+            let names: Vec<String> = language_names(lang.as_str(), &self.header(lang.as_str())?);
+            for entry in entries {
+                if query.matches(&names, &entry.vocab) {
+                    out.push((lang.clone(), entry));
+                }
+            }
+        }
+        Ok(out)
+    }
+
+    /// check the vocabulary of a language for mistakes
+    /// # Arguments
+    /// * `lang`: the language
+    /// * `normalizer`: how names and meanings are compared
+    /// # Returns
+    /// every problem found
+    fn check(&self, lang: &str, normalizer: &Normalizer) -> Result<Vec<Problem>, VctError> {
+        let entries: Vec<Entry> = self.load(lang)?;
+        let header: Header = self.header(lang)?;
+        let mut out: Vec<Problem> = check::whitespace(self, lang, &entries);
+        out.append(&mut check::check_entries(
+            self, lang, &entries, &header, normalizer,
+        ));
+        Ok(out)
+    }
+}
+
+/// open the storage selected by the config and parameters
+/// # Arguments
+/// * `config_dir`: the config directory of vct
+/// * `conf`: the config
+/// * `usedb`: whether the database is used instead of the dict files
+/// * `lenient`: whether invalid lines of dict files are skipped with a warning
+pub fn open(
+    config_dir: &str,
+    conf: &Config,
+    usedb: bool,
+    lenient: bool,
+) -> Result<Box<dyn Storage>, VctError> {
+    if usedb {
+        Ok(Box::new(DbStorage::open(
+            conf.db_path(config_dir).as_str(),
+        )?))
+    } else {
+        Ok(Box::new(DictStorage::new(
+            conf.dict_dir(config_dir),
+            lenient,
+        )))
+    }
+}
+
+/// error returned if a vocab doesn't exist
+fn no_vocab(lang: &str, id: i64) -> VctError {
+    VctError::new(
+        VctErrorKind::ParamError,
+        format!("{} has no vocab with id {}", lang, id).as_str(),
+    )
+}
+
+/// storage keeping every language in a dict file of a directory. The id of a vocab is its line
+/// in the dict file
+pub struct DictStorage {
+    dir: String,
+    /// whether invalid lines are skipped with a warning instead of failing to load
+    lenient: bool,
+}
+
+impl DictStorage {
+    /// create a storage of a dict directory
+    /// # Arguments
+    /// * `dir`: the directory containing the dict files
+    /// * `lenient`: whether invalid lines are skipped with a warning instead of failing to load
+    pub fn new(dir: String, lenient: bool) -> DictStorage {
+        DictStorage { dir, lenient }
+    }
+
+    /// get the path to the dict file of a language
+    fn path(&self, lang: &str) -> String {
+        format!("{}/{}", self.dir, lang)
+    }
+
+    /// read a dict file
+    fn contents(&self, lang: &str) -> Result<String, VctError> {
+        match read_to_string(self.path(lang)) {
+            Ok(n) => Ok(n),
+            Err(_) => Err(VctError::new(
+                VctErrorKind::FileError,
+                "problem opening dictionary file",
+            )),
+        }
+    }
+
+    /// read the lines of a dict file
+    fn lines(&self, lang: &str) -> Result<Vec<String>, VctError> {
+        Ok(self
+            .contents(lang)?
+            .lines()
+            .map(|x| x.to_string())
+            .collect())
+    }
+
+    /// report invalid lines of a dict file
+    /// # Returns
+    /// an error containing every invalid line or nothing if the storage is lenient (the lines are
+    /// printed as warnings instead)
+    fn report(&self, errors: &[LineError]) -> Result<(), VctError> {
+        if errors.is_empty() {
+            return Ok(());
+        }
+        let lines: Vec<String> = errors.iter().map(|x| x.to_string()).collect();
+        if !self.lenient {
+            return Err(VctError::new(
+                VctErrorKind::ParsingError,
+                format!(
+                    "{} invalid lines (use --lenient to skip them)\n{}",
+                    errors.len(),
+                    lines.join("\n")
+                )
+                .as_str(),
+            ));
+        }
+        for line in lines {
+            info::print_info(
+                &Terminal::new(),
+                format!("skipping {}", line),
+                info::MessageType::Warning,
+            );
+        }
+        Ok(())
+    }
+
+    /// create the directories a dict file is in
+    fn create_parent(&self, lang: &str) -> Result<(), VctError> {
+        let path: String = self.path(lang);
+        if let Some(parent) = Path::new(path.as_str()).parent() {
+            if !parent.exists() {
+                if let Err(e) = create_dir_all(parent) {
+                    return Err(VctError::new(
+                        VctErrorKind::FileError,
+                        format!("failed creating necessary directories: {}", e).as_str(),
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// get the index of the line of a vocab in the lines of a dict file
+    fn line_of(lang: &str, lines: &[String], id: i64) -> Result<usize, VctError> {
+        if id < 1 || id as usize > lines.len() || is_ignored(lines[id as usize - 1].as_str()) {
+            return Err(no_vocab(lang, id));
+        }
+        Ok(id as usize - 1)
+    }
+
+    /// replace the contents of a dict file
+    fn write_lines(&self, lang: &str, lines: &[String]) -> Result<(), VctError> {
+        let mut contents: String = lines.join("\n");
+        if !contents.is_empty() {
+            contents.push('\n');
+        }
+        match write(self.path(lang), contents) {
+            Ok(_) => Ok(()),
+            Err(e) => Err(VctError::new(
+                VctErrorKind::FileError,
+                format!("error writing to file: {}", e).as_str(),
+            )),
+        }
+    }
+}
+
+impl Storage for DictStorage {
+    fn languages(&self) -> Result<Vec<String>, VctError> {
+        let mut out: Vec<String> = walk_through_dir(self.dir.clone())
+            .iter()
+            .map(|x| {
+                x.trim_start_matches(self.dir.as_str())
+                    .trim_start_matches('/')
+                    .to_string()
+            })
+            .collect();
+        out.sort();
+        Ok(out)
+    }
+
+    fn load(&self, lang: &str) -> Result<Vec<Entry>, VctError> {
+        let path: String = self.path(lang);
+        let (vocab, errors) = parse_dict(path.as_str(), self.contents(lang)?.as_str());
+        self.report(&errors)?;
+        Ok(vocab
+            .into_iter()
+            .map(|(line, vocab)| Entry {
+                id: line as i64,
+                vocab,
+            })
+            .collect())
+    }
+
+    fn insert(&mut self, lang: &str, vocab: &Vocab) -> Result<i64, VctError> {
+        let path: String = self.path(lang);
+        self.create_parent(lang)?;
+        // make sure the new vocab starts on its own line
+        let mut line: String = vocab.to_line();
+        let mut id: i64 = 1;
+        if let Ok(n) = read_to_string(path.as_str()) {
+            if !n.is_empty() && !n.ends_with('\n') {
+                line = format!("\n{}", line);
+            }
+            id = n.lines().count() as i64 + 1;
+        }
+        let written = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path.as_str())
+            .and_then(|mut x| x.write_all(format!("{}\n", line).as_bytes()));
+        match written {
+            Ok(_) => Ok(id),
+            Err(e) => Err(VctError::new(
+                VctErrorKind::FileError,
+                format!("error writing to file: {}", e).as_str(),
+            )),
+        }
+    }
+
+    fn update(&mut self, lang: &str, id: i64, vocab: &Vocab) -> Result<(), VctError> {
+        let mut lines: Vec<String> = self.lines(lang)?;
+        let idx: usize = DictStorage::line_of(lang, &lines, id)?;
+        lines[idx] = vocab.to_line();
+        self.write_lines(lang, &lines)
+    }
+
+    fn delete(&mut self, lang: &str, id: i64) -> Result<(), VctError> {
+        let mut lines: Vec<String> = self.lines(lang)?;
+        let idx: usize = DictStorage::line_of(lang, &lines, id)?;
+        lines.remove(idx);
+        self.write_lines(lang, &lines)
+    }
+
+    fn remove_language(&mut self, lang: &str) -> Result<(), VctError> {
+        match remove_file(self.path(lang)) {
+            Ok(_) => Ok(()),
+            Err(e) => Err(VctError::new(
+                VctErrorKind::FileError,
+                format!("error removing {}: {}", lang, e).as_str(),
+            )),
+        }
+    }
+
+    fn location(&self, lang: &str, id: i64) -> String {
+        format!("{}:{}", self.path(lang), id)
+    }
+
+    // the line of a vocab changes when a line above it is removed or the file is edited by hand
+    fn stable_ids(&self) -> bool {
+        false
+    }
+
+    fn header(&self, lang: &str) -> Result<Header, VctError> {
+        if !Path::new(self.path(lang).as_str()).exists() {
+            return Ok(Header::default());
+        }
+        let (out, errors) = header::parse(self.path(lang).as_str(), self.contents(lang)?.as_str());
+        self.report(&errors)?;
+        Ok(out)
+    }
+
+    fn set_header(&mut self, lang: &str, header: &Header) -> Result<(), VctError> {
+        self.create_parent(lang)?;
+        let mut lines: Vec<String> = self.lines(lang).unwrap_or_default();
+        // the old header lines are replaced (they are only read before the first vocab)
+        let end: usize = lines
+            .iter()
+            .position(|x| !is_ignored(x))
+            .unwrap_or(lines.len());
+        let mut out: Vec<String> = header.to_lines();
+        out.extend(lines.drain(..end).filter(|x| !is_header(x)));
+        out.append(&mut lines);
+        self.write_lines(lang, &out)
+    }
+
+    fn check(&self, lang: &str, normalizer: &Normalizer) -> Result<Vec<Problem>, VctError> {
+        // invalid lines are reported instead of failing to load
+        let path: String = self.path(lang);
+        let contents: String = self.contents(lang)?;
+        let (header, header_errors) = header::parse(path.as_str(), contents.as_str());
+        let (vocab, errors) = parse_dict(path.as_str(), contents.as_str());
+        let entries: Vec<Entry> = vocab
+            .into_iter()
+            .map(|(line, vocab)| Entry {
+                id: line as i64,
+                vocab,
+            })
+            .collect();
+        let mut out: Vec<Problem> = header_errors
+            .iter()
+            .chain(errors.iter())
+            .map(Problem::from)
+            .collect();
+        out.append(&mut check::trailing_whitespace(
+            path.as_str(),
+            contents.as_str(),
+        ));
+        out.append(&mut check::check_entries(
+            self, lang, &entries, &header, normalizer,
+        ));
+        Ok(out)
+    }
+}
+
+/// storage keeping the vocabulary in the database
+pub struct DbStorage {
+    conn: Connection,
+}
+
+impl DbStorage {
+    /// open (and upgrade if needed) the database
+    /// # Arguments
+    /// * `path`: path to the database
+    pub fn open(path: &str) -> Result<DbStorage, VctError> {
+        Ok(DbStorage {
+            conn: db::open(path)?,
+        })
+    }
+}
+
+impl Storage for DbStorage {
+    fn languages(&self) -> Result<Vec<String>, VctError> {
+        db::languages(&self.conn)
+    }
+
+    fn load(&self, lang: &str) -> Result<Vec<Entry>, VctError> {
+        Ok(db::load(&self.conn, Some(lang))?
+            .into_iter()
+            .map(|(_, id, vocab)| Entry { id, vocab })
+            .collect())
+    }
+
+    fn insert(&mut self, lang: &str, vocab: &Vocab) -> Result<i64, VctError> {
+        self.conn
+            .transaction()
+            .and_then(|tx| {
+                let id: i64 = db::insert(&tx, lang, vocab)?;
+                tx.commit()?;
+                Ok(id)
+            })
+            .map_err(|e| db::db_error("error inserting into database", e))
+    }
+
+    fn update(&mut self, lang: &str, id: i64, vocab: &Vocab) -> Result<(), VctError> {
+        let updated = self
+            .conn
+            .transaction()
+            .and_then(|tx| {
+                let found: bool = db::update(&tx, lang, id, vocab)?;
+                tx.commit()?;
+                Ok(found)
+            })
+            .map_err(|e| db::db_error("error updating vocab", e))?;
+        if updated {
+            Ok(())
+        } else {
+            Err(no_vocab(lang, id))
+        }
+    }
+
+    fn delete(&mut self, lang: &str, id: i64) -> Result<(), VctError> {
+        match db::delete(&self.conn, lang, id) {
+            Ok(true) => Ok(()),
+            Ok(false) => Err(no_vocab(lang, id)),
+            Err(e) => Err(db::db_error("error deleting vocab", e)),
+        }
+    }
+
+    fn remove_language(&mut self, lang: &str) -> Result<(), VctError> {
+        db::remove_language(&self.conn, lang)
+    }
+
+    fn location(&self, _lang: &str, id: i64) -> String {
+        format!("database row {}", id)
+    }
+
+    fn header(&self, lang: &str) -> Result<Header, VctError> {
+        let mut out: Header = Header::default();
+        for (key, value) in db::header(&self.conn, lang)? {
+            // keys are checked when they are written
+            let _ = out.set(key.as_str(), value.as_str());
+        }
+        Ok(out)
+    }
+
+    fn set_header(&mut self, lang: &str, header: &Header) -> Result<(), VctError> {
+        let tx = self
+            .conn
+            .transaction()
+            .map_err(|e| db::db_error("error writing header", e))?;
+        db::set_header(&tx, lang, &header.pairs())
+            .and_then(|_| tx.commit())
+            .map_err(|e| db::db_error("error writing header", e))
+    }
+
+    fn search(&self, query: &Query) -> Result<Vec<(String, Entry)>, VctError> {
+        let mut names: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        let mut out: Vec<(String, Entry)> = Vec::new();
+        for (lang, id, vocab) in db::load(&self.conn, None)? {
+            if !names.contains_key(&lang) {
+                let header: Header = self.header(lang.as_str())?;
+                names.insert(lang.clone(), language_names(lang.as_str(), &header));
+            }
+            if query.matches(&names[&lang], &vocab) {
+                out.push((lang, Entry { id, vocab }));
+            }
+        }
+        Ok(out)
+    }
+}
+
+/// storage keeping the vocabulary in memory (e.g. to try out changes before writing them)
+#[derive(Debug, Clone)]
+pub struct MemoryStorage {
+    langs: BTreeMap<String, Vec<Entry>>,
+    headers: BTreeMap<String, Header>,
+    next_id: i64,
+}
+
+impl MemoryStorage {
+    /// create an empty storage
+    pub fn new() -> MemoryStorage {
+        MemoryStorage {
+            langs: BTreeMap::new(),
+            headers: BTreeMap::new(),
+            next_id: 1,
+        }
+    }
+
+    /// create a storage with a copy of some languages of another storage. The ids of the vocab
+    /// are kept and languages missing in the other storage are created empty
+    /// # Arguments
+    /// * `storage`: the storage to copy from
+    /// * `langs`: the languages to copy
+    pub fn copy_of(storage: &dyn Storage, langs: &[String]) -> Result<MemoryStorage, VctError> {
+        let mut out: MemoryStorage = MemoryStorage::new();
+        let existing: Vec<String> = storage.languages()?;
+        for lang in langs {
+            let entries: Vec<Entry> = if existing.contains(lang) {
+                storage.load(lang.as_str())?
+            } else {
+                Vec::new()
+            };
+            if let Some(n) = entries.iter().map(|x| x.id).max() {
+                out.next_id = out.next_id.max(n + 1);
+            }
+            out.langs.insert(lang.clone(), entries);
+            out.headers
+                .insert(lang.clone(), storage.header(lang.as_str())?);
+        }
+        Ok(out)
+    }
+
+    /// get the entries of a language or an error if the language doesn't exist
+    fn entries(&mut self, lang: &str) -> Result<&mut Vec<Entry>, VctError> {
+        match self.langs.get_mut(lang) {
+            Some(n) => Ok(n),
+            None => Err(VctError::new(
+                VctErrorKind::ParamError,
+                format!("no language {}", lang).as_str(),
+            )),
+        }
+    }
+}
+
+impl Default for MemoryStorage {
+    fn default() -> MemoryStorage {
+        MemoryStorage::new()
+    }
+}
+
+impl Storage for MemoryStorage {
+    fn languages(&self) -> Result<Vec<String>, VctError> {
+        Ok(self.langs.keys().cloned().collect())
+    }
+
+    fn load(&self, lang: &str) -> Result<Vec<Entry>, VctError> {
+        match self.langs.get(lang) {
+            Some(n) => Ok(n.clone()),
+            None => Err(VctError::new(
+                VctErrorKind::ParamError,
+                format!("no language {}", lang).as_str(),
+            )),
+        }
+    }
+
+    fn insert(&mut self, lang: &str, vocab: &Vocab) -> Result<i64, VctError> {
+        let id: i64 = self.next_id;
+        self.next_id += 1;
+        self.langs.entry(lang.to_string()).or_default().push(Entry {
+            id,
+            vocab: vocab.clone(),
+        });
+        Ok(id)
+    }
+
+    fn update(&mut self, lang: &str, id: i64, vocab: &Vocab) -> Result<(), VctError> {
+        match self.entries(lang)?.iter_mut().find(|x| x.id == id) {
+            Some(n) => {
+                n.vocab = vocab.clone();
+                Ok(())
+            }
+            None => Err(no_vocab(lang, id)),
+        }
+    }
+
+    fn delete(&mut self, lang: &str, id: i64) -> Result<(), VctError> {
+        let entries: &mut Vec<Entry> = self.entries(lang)?;
+        match entries.iter().position(|x| x.id == id) {
+            Some(n) => {
+                entries.remove(n);
+                Ok(())
+            }
+            None => Err(no_vocab(lang, id)),
+        }
+    }
+
+    fn remove_language(&mut self, lang: &str) -> Result<(), VctError> {
+        self.langs.remove(lang);
+        self.headers.remove(lang);
+        Ok(())
+    }
+
+    fn header(&self, lang: &str) -> Result<Header, VctError> {
+        Ok(self.headers.get(lang).cloned().unwrap_or_default())
+    }
+
+    fn set_header(&mut self, lang: &str, header: &Header) -> Result<(), VctError> {
+        self.langs.entry(lang.to_string()).or_default();
+        self.headers.insert(lang.to_string(), header.clone());
+        Ok(())
+    }
+}